@@ -1,14 +1,20 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
+use std::str::FromStr;
 
 use num::rational::BigRational;
-use num::One;
+use num::{One, ToPrimitive};
 use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 
+use symbolic_ga::basis::Vector;
+use symbolic_ga::conformal::Conformal;
 use symbolic_ga::element::Element;
+use symbolic_ga::function::Function;
 use symbolic_ga::multivector::MultiVector;
-use symbolic_ga::symbols::{lift_integer, SymbolPowers, Symbols};
+use symbolic_ga::polynomial::Polynomial;
+use symbolic_ga::symbols::{lift_integer, Symbols};
 
+use crate::term::{SharedTerms, Term};
 use crate::tokens::tokenstream_push;
 use crate::types::{element_term_name, element_type_name};
 use crate::{CodeBasis, Expr, MVType};
@@ -19,35 +25,147 @@ pub fn simplify_expr(
     expr: &Expr,
 ) -> Result<MultiVector, String> {
     match expr {
-        Expr::Symbol(sym) => Ok(symbol_as_mv(sym_types, sym)),
+        Expr::Brackets(expr) => simplify_expr(basis, sym_types, expr),
+        Expr::Element(vs) => mv_from_vectors(basis, vs),
+        Expr::Symbol(sym) => Ok(symbol_as_mv(basis, sym_types, sym)),
         Expr::Constant(x) => Ok(mv_from_scalar(*x)),
+        Expr::Negate(expr) => {
+            simplify_expr(basis, sym_types, expr).map(|mv| mv.scale(&lift_integer(-1)))
+        }
         Expr::Add(a, b) => {
             let mv_a = simplify_expr(basis, sym_types, a)?;
             let mv_b = simplify_expr(basis, sym_types, b)?;
             Ok(mv_a + mv_b)
         }
+        Expr::Sub(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            Ok(mv_a + mv_b.scale(&lift_integer(-1)))
+        }
         Expr::Mul(a, b) => {
             let mv_a = simplify_expr(basis, sym_types, a)?;
             let mv_b = simplify_expr(basis, sym_types, b)?;
             mv_a.multiply(&basis.basis, &mv_b)
         }
+        Expr::Div(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            mv_a.multiply(&basis.basis, &mv_b.inverse(&basis.basis)?)
+        }
+        Expr::Outer(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            mv_a.outer(&basis.basis, &mv_b)
+        }
+        Expr::Regressive(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            mv_a.regressive(&basis.basis, &mv_b)
+        }
+        Expr::Commutator(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            mv_a.commutator(&basis.basis, &mv_b)
+        }
+        Expr::AntiCommutator(a, b) => {
+            let mv_a = simplify_expr(basis, sym_types, a)?;
+            let mv_b = simplify_expr(basis, sym_types, b)?;
+            mv_a.anti_commutator(&basis.basis, &mv_b)
+        }
+        Expr::Sandwich(versor, x) => {
+            let mv_versor = simplify_expr(basis, sym_types, versor)?;
+            let mv_x = simplify_expr(basis, sym_types, x)?;
+            // An argument keeps the grades of its declared type
+            let grades = match x.as_ref() {
+                Expr::Symbol(sym) => sym_types
+                    .get(sym)
+                    .map(|mv_type| mv_type.0.iter().map(Element::grade).collect())
+                    .unwrap_or_else(|| mv_x.grades()),
+                _ => mv_x.grades(),
+            };
+            mv_versor.sandwich_to(&basis.basis, &mv_x, &grades)
+        }
+        Expr::Reverse(expr) => simplify_expr(basis, sym_types, expr).map(|mv| mv.reverse()),
+        Expr::Involute(expr) => {
+            simplify_expr(basis, sym_types, expr).map(|mv| mv.grade_involution())
+        }
+        Expr::Conjugate(expr) => {
+            simplify_expr(basis, sym_types, expr).map(|mv| mv.clifford_conjugate())
+        }
+        Expr::Exp(expr) => simplify_expr(basis, sym_types, expr)?.exp(&basis.basis),
+        Expr::Log(expr) => simplify_expr(basis, sym_types, expr)?.log(&basis.basis),
+        Expr::Pow(expr, n) => simplify_expr(basis, sym_types, expr)?.pow(&basis.basis, *n),
+        Expr::Up(expr) => conformal(basis, "up")?.up(&simplify_expr(basis, sym_types, expr)?),
+        Expr::Down(expr) => conformal(basis, "down")?.down(&simplify_expr(basis, sym_types, expr)?),
+        Expr::Apply(function, args) => {
+            let mut scalars = Vec::new();
+            for arg in args.iter() {
+                let scalar = simplify_expr(basis, sym_types, arg)?
+                    .scalar_part()
+                    .ok_or_else(|| format!("{} needs scalar arguments", function.name()))?;
+                scalars.push(scalar);
+            }
+            Ok(mv_from_symbols(Symbols::apply(*function, scalars)))
+        }
         Expr::Grade(expr, grades) => {
             simplify_expr(basis, sym_types, expr).map(|mv| mv.project(&basis.basis, grades))
         }
-        _ => todo!("simplify expr"),
     }
 }
 
+pub fn reciprocal_name(idx: usize) -> String {
+    format!("__recip_{}", idx)
+}
+
+pub fn subterm_name(idx: usize) -> String {
+    format!("__sub_{}", idx)
+}
+
+/// The result expression. Each distinct denominator is inverted once up front and shared by
+/// every component that divides by it, and subterms written out more than once anywhere in
+/// the result are bound to variables first.
 pub fn mv_as_code(basis: &CodeBasis, mv: &MultiVector) -> TokenStream {
+    typed_mv_as_code(basis, mv, &MVType(mv.0.keys().cloned().collect()))
+}
+
+/// Like `mv_as_code` but with a component for every element of `mv_type`, zero when missing.
+/// Components are written for the oriented blades of the basis.
+pub fn typed_mv_as_code(basis: &CodeBasis, mv: &MultiVector, mv_type: &MVType) -> TokenStream {
+    let mv = &mv.orient(&basis.basis);
+    let mut denoms: Vec<&Polynomial> = Vec::new();
+    for syms in mv.0.values() {
+        if !syms.is_polynomial() && !denoms.contains(&&syms.denom) {
+            denoms.push(&syms.denom);
+        }
+    }
+    let denom_terms: Vec<Term> = denoms.iter().map(|d| Term::from_polynomial(d)).collect();
+
+    let zero = Symbols::zero();
+    let components: Vec<(&Element, &Symbols, Term)> = mv_type
+        .0
+        .iter()
+        .map(|elem| {
+            let syms = mv.0.get(elem).unwrap_or(&zero);
+            (elem, syms, Term::from_polynomial(&syms.numer))
+        })
+        .collect();
+
+    let shared = SharedTerms::new(
+        denom_terms
+            .iter()
+            .chain(components.iter().map(|(_elem, _syms, term)| term)),
+    );
+
     let mut tokens = TokenStream::new();
 
-    for (elem, syms) in mv.0.iter() {
+    for (elem, syms, numer) in components.iter() {
         if !tokens.is_empty() {
             tokenstream_push(&mut tokens, Punct::new(',', Spacing::Alone).into());
         }
 
+        let value = symbols_as_code(&basis.scalar, syms, numer, &denoms, &shared);
         if elem.0.is_empty() {
-            tokens.extend(symbols_as_code(syms))
+            tokens.extend(value)
         } else {
             let type_name = element_type_name(basis, elem);
             tokenstream_push(
@@ -56,99 +174,650 @@ pub fn mv_as_code(basis: &CodeBasis, mv: &MultiVector) -> TokenStream {
             );
             tokenstream_push(
                 &mut tokens,
-                Group::new(Delimiter::Parenthesis, symbols_as_code(syms)).into(),
+                Group::new(Delimiter::Parenthesis, value).into(),
             );
         }
     }
 
-    std::iter::once::<TokenTree>(Group::new(Delimiter::Parenthesis, tokens).into()).collect()
+    let result: TokenStream =
+        std::iter::once::<TokenTree>(Group::new(Delimiter::Parenthesis, tokens).into()).collect();
+
+    if denoms.is_empty() && shared.terms().is_empty() {
+        return result;
+    }
+
+    let mut tokens = TokenStream::new();
+
+    for (idx, term) in shared.terms().iter().enumerate() {
+        let binding = format!("let {} = ", subterm_name(idx));
+        tokens.extend(TokenStream::from_str(&binding).expect("Creating subterm binding"));
+        tokens.extend(expanded_term_as_code(&basis.scalar, term, &shared));
+        tokenstream_push(&mut tokens, Punct::new(';', Spacing::Alone).into());
+    }
+
+    for (idx, denom) in denom_terms.iter().enumerate() {
+        let binding = format!("let {} = 1.0 / ", reciprocal_name(idx));
+        tokens.extend(TokenStream::from_str(&binding).expect("Creating reciprocal binding"));
+        tokenstream_push(
+            &mut tokens,
+            Group::new(
+                Delimiter::Parenthesis,
+                term_as_code(&basis.scalar, denom, &shared),
+            )
+            .into(),
+        );
+        tokenstream_push(&mut tokens, Punct::new(';', Spacing::Alone).into());
+    }
+
+    tokens.extend(result);
+
+    std::iter::once::<TokenTree>(Group::new(Delimiter::Brace, tokens).into()).collect()
 }
 
-fn symbol_as_mv(sym_types: &BTreeMap<String, MVType>, sym: &str) -> MultiVector {
+fn symbol_as_mv(basis: &CodeBasis, sym_types: &BTreeMap<String, MVType>, sym: &str) -> MultiVector {
     // Should have been checked before code gen
     let types = sym_types.get(sym).expect("Symbol missing in context");
 
     let mut mv = MultiVector::default();
 
     for elem in types.0.iter() {
-        mv = mv + mv_from_symbol(format!("{}_{}", sym, element_term_name(elem)), elem.clone());
+        mv = mv
+            + mv_from_symbol(
+                format!("{}_{}", sym, element_term_name(basis, elem)),
+                elem.clone(),
+            );
     }
 
-    mv
+    // Arguments hold coefficients of the oriented blades
+    mv.orient(&basis.basis)
 }
 
-fn symbols_as_code(syms: &Symbols) -> TokenStream {
-    let mut tokens = TokenStream::new();
+/// A numerator over a shared denominator, multiplied by that denominator's reciprocal binding.
+fn symbols_as_code(
+    scalar: &str,
+    syms: &Symbols,
+    numer: &Term,
+    denoms: &[&Polynomial],
+    shared: &SharedTerms,
+) -> TokenStream {
+    if syms.is_polynomial() {
+        return term_as_code(scalar, numer, shared);
+    }
 
-    for (powers, scale) in syms.0.iter() {
-        if !tokens.is_empty() {
-            tokenstream_push(&mut tokens, Punct::new('+', Spacing::Alone).into());
+    let idx = denoms
+        .iter()
+        .position(|denom| *denom == &syms.denom)
+        .expect("Denominator was collected");
+
+    let mut tokens: TokenStream = std::iter::once::<TokenTree>(
+        Group::new(Delimiter::Parenthesis, term_as_code(scalar, numer, shared)).into(),
+    )
+    .collect();
+    tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
+    tokenstream_push(
+        &mut tokens,
+        Ident::new(&reciprocal_name(idx), Span::call_site()).into(),
+    );
+
+    tokens
+}
+
+/// A shared term is its binding, anything else is written out.
+fn term_as_code(scalar: &str, term: &Term, shared: &SharedTerms) -> TokenStream {
+    match shared.index(term) {
+        Some(idx) => {
+            std::iter::once::<TokenTree>(Ident::new(&subterm_name(idx), Span::call_site()).into())
+                .collect()
         }
+        None => expanded_term_as_code(scalar, term, shared),
+    }
+}
 
-        if !scale.is_one() || powers.is_empty() {
-            tokens.extend(rational_as_code(scale));
+/// Writes out the top of a term, function applications become calls like `f32::sqrt(x)`.
+fn expanded_term_as_code(scalar: &str, term: &Term, shared: &SharedTerms) -> TokenStream {
+    let mut tokens = TokenStream::new();
 
-            if !powers.is_empty() {
-                tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
-            }
+    match term {
+        Term::Constant(value) => tokens.extend(rational_as_code(value)),
+        Term::Variable(name) => {
+            tokenstream_push(&mut tokens, Ident::new(name, Span::call_site()).into())
         }
+        // Only the chosen branch is used, the other may be NaN
+        Term::Call(Function::IfZero, args) => {
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| term_as_code(scalar, arg, shared).to_string())
+                .collect();
+            tokens.extend(
+                TokenStream::from_str(&format!(
+                    "(if {} == 0.0 {{ {} }} else {{ {} }})",
+                    args[0], args[1], args[2]
+                ))
+                .expect("Creating branch"),
+            );
+        }
+        Term::Call(function, args) => {
+            tokens.extend(
+                TokenStream::from_str(&format!("{}::{}", scalar, function.name()))
+                    .expect("Creating function path"),
+            );
 
-        tokens.extend(powers_as_code(powers));
+            let mut arg_tokens = TokenStream::new();
+            for arg in args.iter() {
+                if !arg_tokens.is_empty() {
+                    tokenstream_push(&mut arg_tokens, Punct::new(',', Spacing::Alone).into());
+                }
+                arg_tokens.extend(term_as_code(scalar, arg, shared));
+            }
+            tokenstream_push(
+                &mut tokens,
+                Group::new(Delimiter::Parenthesis, arg_tokens).into(),
+            );
+        }
+        // Higher powers by repeated squaring rather than a long chain of multiplies, powers past
+        // `i32::MAX` multiply several calls
+        Term::Power(base, pow) if *pow > 3 => {
+            let mut rest = *pow;
+            while rest > 0 {
+                let chunk = i32::try_from(rest).unwrap_or(i32::MAX);
+                if !tokens.is_empty() {
+                    tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
+                }
+                tokens.extend(
+                    TokenStream::from_str(&format!("{}::powi", scalar))
+                        .expect("Creating powi path"),
+                );
+                let mut arg_tokens = term_as_code(scalar, base, shared);
+                tokenstream_push(&mut arg_tokens, Punct::new(',', Spacing::Alone).into());
+                tokenstream_push(&mut arg_tokens, Literal::i32_unsuffixed(chunk).into());
+                tokenstream_push(
+                    &mut tokens,
+                    Group::new(Delimiter::Parenthesis, arg_tokens).into(),
+                );
+                rest -= chunk as usize;
+            }
+        }
+        Term::Power(base, pow) => {
+            for _ in 0..*pow {
+                if !tokens.is_empty() {
+                    tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
+                }
+                tokens.extend(factor_as_code(scalar, base, shared));
+            }
+        }
+        Term::Product(factors) => {
+            for factor in factors.iter() {
+                if !tokens.is_empty() {
+                    tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
+                }
+                tokens.extend(factor_as_code(scalar, factor, shared));
+            }
+        }
+        Term::Sum(summands) => {
+            for (negative, summand) in summands.iter() {
+                if *negative {
+                    tokenstream_push(&mut tokens, Punct::new('-', Spacing::Alone).into());
+                } else if !tokens.is_empty() {
+                    tokenstream_push(&mut tokens, Punct::new('+', Spacing::Alone).into());
+                }
+                tokens.extend(term_as_code(scalar, summand, shared));
+            }
+        }
+        Term::Quotient(numer, denom) => {
+            for (idx, part) in [numer, denom].iter().enumerate() {
+                if idx > 0 {
+                    tokenstream_push(&mut tokens, Punct::new('/', Spacing::Alone).into());
+                }
+                tokenstream_push(
+                    &mut tokens,
+                    Group::new(Delimiter::Parenthesis, term_as_code(scalar, part, shared)).into(),
+                );
+            }
+        }
+        Term::Negate(term) => {
+            tokenstream_push(&mut tokens, Punct::new('-', Spacing::Alone).into());
+            tokens.extend(factor_as_code(scalar, term, shared));
+        }
     }
 
     tokens
 }
 
-fn rational_as_code(rat: &BigRational) -> TokenStream {
-    let numer = usize::try_from(rat.numer()).expect("Could not convert numer from big rational");
-    if rat.denom().is_one() {
-        std::iter::once::<TokenTree>(Literal::f64_unsuffixed(numer as f64).into()).collect()
-    } else {
-        todo!("rational as code")
+/// A term inside a product, bracketed unless it binds tighter than `*`.
+fn factor_as_code(scalar: &str, term: &Term, shared: &SharedTerms) -> TokenStream {
+    let code = term_as_code(scalar, term, shared);
+    match term {
+        Term::Sum(_) | Term::Quotient(_, _) | Term::Negate(_) if shared.index(term).is_none() => {
+            std::iter::once::<TokenTree>(Group::new(Delimiter::Parenthesis, code).into()).collect()
+        }
+        _ => code,
     }
 }
 
-fn powers_as_code(powers: &SymbolPowers) -> TokenStream {
-    let mut tokens = TokenStream::new();
+fn rational_as_code(rat: &BigRational) -> TokenStream {
+    let numer = rat
+        .numer()
+        .to_f64()
+        .expect("Could not convert numer from big rational");
+    let mut tokens: TokenStream =
+        std::iter::once::<TokenTree>(Literal::f64_unsuffixed(numer).into()).collect();
 
-    for (sym, pow) in powers.iter() {
-        for _ in 0..*pow {
-            if !tokens.is_empty() {
-                tokenstream_push(&mut tokens, Punct::new('*', Spacing::Alone).into());
-            }
-            tokenstream_push(&mut tokens, Ident::new(sym, Span::call_site()).into());
-        }
+    if !rat.denom().is_one() {
+        let denom = rat
+            .denom()
+            .to_f64()
+            .expect("Could not convert denom from big rational");
+        tokenstream_push(&mut tokens, Punct::new('/', Spacing::Alone).into());
+        tokenstream_push(&mut tokens, Literal::f64_unsuffixed(denom).into());
     }
 
     tokens
 }
 
+fn conformal(basis: &CodeBasis, function: &str) -> Result<Conformal, String> {
+    Conformal::from_basis(&basis.basis)
+        .ok_or_else(|| format!("{} needs a conformal basis", function))
+}
+
+fn mv_from_symbols(syms: Symbols) -> MultiVector {
+    if syms.is_zero() {
+        MultiVector::default()
+    } else {
+        MultiVector(vec![(Element(BTreeSet::new()), syms)].into_iter().collect())
+    }
+}
+
 fn mv_from_scalar(x: isize) -> MultiVector {
     MultiVector(
-        vec![(
-            Element(BTreeSet::new()),
-            Symbols(
-                vec![(BTreeMap::new(), lift_integer(x))]
-                    .into_iter()
-                    .collect(),
-            ),
-        )]
-        .into_iter()
-        .collect(),
+        vec![(Element(BTreeSet::new()), Symbols::constant(lift_integer(x)))]
+            .into_iter()
+            .collect(),
     )
 }
 
+fn mv_from_vectors(basis: &CodeBasis, vs: &[Vector]) -> Result<MultiVector, String> {
+    let one = Symbols::constant(lift_integer(1));
+    vs.iter().try_fold(mv_from_scalar(1), |mv, v| {
+        let rhs = MultiVector(vec![(Element::from(*v), one.clone())].into_iter().collect());
+        mv.multiply(&basis.basis, &rhs)
+    })
+}
+
 fn mv_from_symbol(x: String, elem: Element) -> MultiVector {
-    MultiVector(
-        vec![(
-            elem,
-            Symbols(
-                vec![(vec![(x, 1)].into_iter().collect(), lift_integer(1))]
-                    .into_iter()
-                    .collect(),
-            ),
-        )]
-        .into_iter()
-        .collect(),
-    )
+    MultiVector(vec![(elem, Symbols::symbol(&x))].into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use num::Signed;
+    use symbolic_ga::basis::Basis;
+
+    use super::*;
+    use crate::parse::expr::parse_expression;
+
+    const G3: Basis = Basis::new(0, 3, 0);
+
+    fn vector_elems(elems: &[&[usize]]) -> BTreeSet<Element> {
+        elems
+            .iter()
+            .map(|vs| Element(vs.iter().cloned().map(Vector).collect()))
+            .collect()
+    }
+
+    fn simplify_src(args: &[(&str, &[&[usize]])], src: &str) -> Result<MultiVector, String> {
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let sym_types = args
+            .iter()
+            .map(|(name, elems)| (name.to_string(), MVType(vector_elems(elems))))
+            .collect();
+        let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+        simplify_expr(&basis, &sym_types, &parse_expression(&basis, &mut tokens)?)
+    }
+
+    #[test]
+    fn test_rotor_sandwich_keeps_vectors() -> Result<(), String> {
+        let rotor: &[&[usize]] = &[&[], &[0, 1], &[0, 2], &[1, 2]];
+        let vector: &[&[usize]] = &[&[0], &[1], &[2]];
+
+        let mv = simplify_src(&[("r", rotor), ("x", vector)], "r * x * ~r")?;
+
+        // The trivector terms cancel, leaving a vector
+        assert!(mv.0.keys().all(|elem| elem.grade() == 1));
+        assert_eq!(mv.0.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_commutator_matches_expanded_form() -> Result<(), String> {
+        let bivector: &[&[usize]] = &[&[0, 1], &[0, 2], &[1, 2]];
+        let args = [("a", bivector), ("b", bivector)];
+
+        let commutator = simplify_src(&args, "commutator(a, b)")?;
+        let anti_commutator = simplify_src(&args, "anticommutator(a, b)")?;
+
+        assert_eq!(
+            commutator.scale(&lift_integer(2)),
+            simplify_src(&args, "a * b - b * a")?
+        );
+        assert_eq!(
+            anti_commutator.scale(&lift_integer(2)),
+            simplify_src(&args, "a * b + b * a")?
+        );
+        assert_eq!(commutator + anti_commutator, simplify_src(&args, "a * b")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sandwich_keeps_argument_grades() -> Result<(), String> {
+        let even: &[&[usize]] = &[&[], &[0, 1], &[0, 1, 2]];
+        let bivector: &[&[usize]] = &[&[0, 1], &[0, 2], &[1, 2]];
+        let args = [("r", even), ("b", bivector)];
+
+        let sandwich = simplify_src(&args, "sandwich(r, b)")?;
+        let full = simplify_src(&args, "r * b * ~r")?;
+
+        assert!(full.0.keys().any(|elem| elem.grade() != 2));
+        assert_eq!(sandwich, full.project(&G3, &vec![2].into_iter().collect()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sandwich_reflects_in_vector() -> Result<(), String> {
+        let vector: &[&[usize]] = &[&[0], &[1], &[2]];
+        let args = [("n", vector), ("x", vector)];
+
+        // -n x n, the reflection in the plane orthogonal to n
+        assert_eq!(
+            simplify_src(&args, "sandwich(n, x)")?,
+            simplify_src(&args, "-1 * n * x * n")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_divide_by_constant_is_exact() -> Result<(), String> {
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let args = [("x", vector)];
+
+        let half = BigRational::new(1.into(), 2.into());
+        assert_eq!(
+            simplify_src(&args, "x / 2")?,
+            simplify_src(&args, "x")?.scale(&half)
+        );
+        // e1 + e2 squares to 2
+        assert_eq!(
+            simplify_src(&args, "x / (e0 + e1)")?,
+            simplify_src(&args, "x * (e0 + e1)")?.scale(&half)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_divide_shares_denominator() -> Result<(), String> {
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let mv = simplify_src(&[("x", vector)], "1 / x")?;
+
+        assert_eq!(
+            mv_as_code(&basis, &mv).to_string(),
+            "{ let __recip_0 = 1.0 / (x_e0 * x_e0 + x_e1 * x_e1) ; \
+             (E0 ((x_e0) * __recip_0) , E1 ((x_e1) * __recip_0)) }"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exp_calls_scalar_functions() -> Result<(), String> {
+        let bivector: &[&[usize]] = &[&[0, 1]];
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f64".to_string(),
+            types: BTreeMap::new(),
+        };
+        let mv = simplify_src(&[("b", bivector)], "exp(b)")?;
+
+        assert_eq!(
+            mv_as_code(&basis, &mv).to_string(),
+            "{ let __sub_0 = b_e0e1 * b_e0e1 ; \
+             let __sub_1 = f64 :: sqrt (__sub_0) ; \
+             (f64 :: cos (__sub_1) , \
+             E0E1 (b_e0e1 * (if __sub_0 == 0.0 { 1.0 } \
+             else { (f64 :: sin (__sub_1)) / (__sub_1) }))) }"
+        );
+        assert!(simplify_src(&[("b", bivector)], "exp(b + e0)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scalar_functions() -> Result<(), String> {
+        let scalar: &[&[usize]] = &[&[]];
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let args = [("a", scalar), ("b", scalar), ("x", vector)];
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+
+        let normalized = simplify_src(&args, "x / sqrt(x * x)")?;
+        assert_eq!(
+            mv_as_code(&basis, &normalized).to_string(),
+            "{ let __recip_0 = 1.0 / (f32 :: sqrt (x_e0 * x_e0 + x_e1 * x_e1)) ; \
+             (E0 ((x_e0) * __recip_0) , E1 ((x_e1) * __recip_0)) }"
+        );
+        assert_eq!(
+            simplify_src(&args, "sqrt(x * x) * sqrt(x * x)")?,
+            simplify_src(&args, "x * x")?
+        );
+        assert_eq!(
+            simplify_src(&args, "sin(a) * sin(a) + cos(a) * cos(a)")?,
+            simplify_src(&args, "1")?
+        );
+        assert_eq!(
+            mv_as_code(&basis, &simplify_src(&args, "atan2(a, b / 2)")?).to_string(),
+            "(f32 :: atan2 (a_1 , 1.0 / 2.0 * b_1))"
+        );
+
+        assert!(simplify_src(&args, "sqrt(x)").is_err());
+        assert!(simplify_src(&args, "atan2(a)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pow_expands_symbolically() -> Result<(), String> {
+        let scalar: &[&[usize]] = &[&[]];
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let args = [("a", scalar), ("x", vector)];
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            simplify_src(&args, "pow(x, 3)")?,
+            simplify_src(&args, "x * x * x")?
+        );
+        assert_eq!(simplify_src(&args, "pow(x, 0)")?, simplify_src(&args, "1")?);
+        assert_eq!(
+            simplify_src(&args, "pow(x, -2) * x * x")?,
+            simplify_src(&args, "1")?
+        );
+        assert_eq!(
+            mv_as_code(&basis, &simplify_src(&args, "pow(a, 5)")?).to_string(),
+            "(f32 :: powi (a_1 , 5))"
+        );
+
+        // Past i32::MAX the power is split rather than wrapped
+        let base = Box::new(Term::Variable("a".to_string()));
+        let huge = Term::Power(base, i32::MAX as usize + 5);
+        assert_eq!(
+            expanded_term_as_code("f32", &huge, &SharedTerms::default()).to_string(),
+            "f32 :: powi (a , 2147483647) * f32 :: powi (a , 5)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conformal_up_down() -> Result<(), String> {
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let sym_types = std::iter::once(("x".to_string(), MVType(vector_elems(vector)))).collect();
+        for conformal in [Conformal::new(2), Conformal::null(2)].iter() {
+            let basis = CodeBasis {
+                basis: conformal.basis(),
+                scalar: "f32".to_string(),
+                types: BTreeMap::new(),
+            };
+            let simplify = |src: &str| {
+                let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+                simplify_expr(&basis, &sym_types, &parse_expression(&basis, &mut tokens)?)
+            };
+            assert_eq!(simplify("down(3 * up(x))")?, simplify("x")?);
+            assert_eq!(simplify("up(e0) * up(e0)")?, MultiVector::default());
+        }
+
+        // Not a conformal basis, even with the signature of one
+        assert!(simplify_src(&[], "up(e0)").is_err());
+        let minkowski = CodeBasis {
+            basis: Basis::new(0, 3, 1),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let mut tokens = TokenStream::from_str("up(e0)")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        let up = parse_expression(&minkowski, &mut tokens)?;
+        assert!(simplify_expr(&minkowski, &sym_types, &up).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_divide_cancels_common_factors() -> Result<(), String> {
+        let scalar: &[&[usize]] = &[&[]];
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let args = [("a", scalar), ("x", vector)];
+
+        assert_eq!(
+            simplify_src(&args, "(a * x) / a")?,
+            simplify_src(&args, "x")?
+        );
+        assert_eq!(
+            simplify_src(&args, "x / (x * x) * x * x")?,
+            simplify_src(&args, "x")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_divide_by_non_invertible_fails() {
+        // (1 + e0)(1 - e0) = 0
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        assert!(simplify_src(&[("x", vector)], "x / (1 + e0)").is_err());
+        assert!(simplify_src(&[("x", vector)], "x / (e0 - e0)").is_err());
+    }
+
+    #[test]
+    fn test_rational_as_code() {
+        let half = BigRational::new(1.into(), 2.into());
+        assert_eq!(rational_as_code(&half).to_string(), "1.0 / 2.0");
+        assert_eq!(rational_as_code(&lift_integer(3)).to_string(), "3.0");
+    }
+
+    #[test]
+    fn test_involutions_agree_with_multivector() -> Result<(), String> {
+        let full: &[&[usize]] = &[&[], &[0], &[1, 2], &[0, 1, 2]];
+        let x = simplify_src(&[("x", full)], "x")?;
+
+        assert_eq!(simplify_src(&[("x", full)], "~x")?, x.reverse());
+        assert_eq!(simplify_src(&[("x", full)], "reverse(x)")?, x.reverse());
+        assert_eq!(
+            simplify_src(&[("x", full)], "involute(x)")?,
+            x.grade_involution()
+        );
+        assert_eq!(
+            simplify_src(&[("x", full)], "conjugate(x)")?,
+            x.clifford_conjugate()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_subterms_become_bindings() -> Result<(), String> {
+        let scalar: &[&[usize]] = &[&[]];
+        let vector: &[&[usize]] = &[&[0], &[1]];
+        let args = [("a", scalar), ("b", scalar), ("x", vector)];
+        let basis = CodeBasis {
+            basis: G3,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+
+        // a a x + a b = a (a x + b), with the common factor in both components
+        assert_eq!(
+            mv_as_code(&basis, &simplify_src(&args, "(a * a + b) * x * a")?).to_string(),
+            "{ let __sub_0 = a_1 * a_1 + b_1 ; \
+             (E0 (a_1 * x_e0 * __sub_0) , E1 (a_1 * x_e1 * __sub_0)) }"
+        );
+        assert_eq!(
+            mv_as_code(&basis, &simplify_src(&args, "a * a * a - 2 * a * b")?).to_string(),
+            "(a_1 * (a_1 * a_1 - 2.0 * b_1))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_motor_sandwich_saves_multiplies() -> Result<(), String> {
+        let pga3 = CodeBasis {
+            basis: Basis::new(1, 3, 0),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let motor: &[&[usize]] = &[
+            &[],
+            &[0, 1],
+            &[0, 2],
+            &[0, 3],
+            &[1, 2],
+            &[1, 3],
+            &[2, 3],
+            &[0, 1, 2, 3],
+        ];
+        let point: &[&[usize]] = &[&[0, 1, 2], &[0, 1, 3], &[0, 2, 3], &[1, 2, 3]];
+        let mut tokens = TokenStream::from_str("sandwich(m, p)")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        let sym_types = [("m", motor), ("p", point)]
+            .iter()
+            .map(|(name, elems)| (name.to_string(), MVType(vector_elems(elems))))
+            .collect();
+        let mv = simplify_expr(&pga3, &sym_types, &parse_expression(&pga3, &mut tokens)?)?;
+
+        // Written out, every monomial takes one multiply per factor after the first
+        let expanded: usize =
+            mv.0.values()
+                .flat_map(|syms| syms.numer.0.iter())
+                .map(|(powers, scale)| {
+                    powers.values().sum::<usize>() - 1 + if scale.abs().is_one() { 0 } else { 1 }
+                })
+                .sum();
+        let code = mv_as_code(&pga3, &mv).to_string();
+        let multiplies = code.matches('*').count();
+        assert!(
+            multiplies * 3 < expanded * 2,
+            "{} multiplies, {} expanded",
+            multiplies,
+            expanded
+        );
+        Ok(())
+    }
 }
@@ -2,13 +2,17 @@ use std::collections::BTreeMap;
 
 use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
 
-use crate::expr::{mv_as_code, simplify_expr};
+use symbolic_ga::relations::Relations;
+use symbolic_ga::symbols::Symbol;
+
+use crate::expr::{mv_as_code, simplify_expr, typed_mv_as_code};
 use crate::tokens::tokenstream_push;
 use crate::types::{element_term_name, element_type_name, type_signiture};
-use crate::{CodeBasis, Expr, MVType};
+use crate::{Assumption, CodeBasis, Expr, MVType};
 
 pub struct Lambda {
     args: Vec<(String, MVType)>,
+    assumptions: Vec<(String, Assumption)>,
     body: Expr,
 }
 
@@ -17,6 +21,10 @@ impl Lambda {
         &self.args
     }
 
+    pub fn assumptions(&self) -> &Vec<(String, Assumption)> {
+        &self.assumptions
+    }
+
     pub fn body(&self) -> &Expr {
         &self.body
     }
@@ -26,22 +34,91 @@ impl Lambda {
         //      Must not conflict with suffixed names
         // TODO check expression in valid in ctx
 
-        Ok(Lambda { args, body })
+        Ok(Lambda {
+            args,
+            assumptions: Vec::new(),
+            body,
+        })
+    }
+
+    /// Results are simplified assuming each named argument satisfies its assumption.
+    pub fn with_assumptions(self, assumptions: Vec<(String, Assumption)>) -> Lambda {
+        Lambda {
+            assumptions,
+            ..self
+        }
     }
 
     pub fn as_code(&self, basis: &CodeBasis) -> Result<TokenStream, String> {
-        let mut tokens = TokenStream::new();
-        tokenstream_push(&mut tokens, Punct::new('|', Spacing::Alone).into());
-        tokens.extend(args_as_code(basis, &self.args));
-        tokenstream_push(&mut tokens, Punct::new('|', Spacing::Alone).into());
+        let mut tokens = self.head_as_code(basis);
 
         let sym_types: BTreeMap<String, MVType> = self.args.iter().cloned().collect();
 
-        let mv = simplify_expr(basis, &sym_types, &self.body)?;
+        let relations = self.relations(basis, &sym_types)?;
+        let mv = simplify_expr(basis, &sym_types, &self.body)?.reduce(&relations);
         tokens.extend(mv_as_code(basis, &mv));
 
         Ok(tokens)
     }
+
+    /// A closure returning the derivatives of the body with respect to each component of the
+    /// argument `wrt`, all with the type of the body.
+    pub fn jacobian_as_code(&self, basis: &CodeBasis, wrt: &str) -> Result<TokenStream, String> {
+        let wrt_type = self
+            .args
+            .iter()
+            .find(|(name, _mv_type)| name == wrt)
+            .map(|(_name, mv_type)| mv_type)
+            .ok_or_else(|| format!("No argument named '{}' to differentiate by", wrt))?;
+
+        let mut tokens = self.head_as_code(basis);
+
+        let sym_types: BTreeMap<String, MVType> = self.args.iter().cloned().collect();
+
+        let relations = self.relations(basis, &sym_types)?;
+        let mv = simplify_expr(basis, &sym_types, &self.body)?;
+        let result_type = MVType(mv.0.keys().cloned().collect());
+
+        let mut derivatives = TokenStream::new();
+        for elem in wrt_type.0.iter() {
+            if !derivatives.is_empty() {
+                tokenstream_push(&mut derivatives, Punct::new(',', Spacing::Alone).into());
+            }
+            let var = Symbol::Named(format!("{}_{}", wrt, element_term_name(basis, elem)));
+            let derivative = mv.derivative(&var)?.reduce(&relations);
+            derivatives.extend(typed_mv_as_code(basis, &derivative, &result_type));
+        }
+        tokenstream_push(
+            &mut tokens,
+            Group::new(Delimiter::Parenthesis, derivatives).into(),
+        );
+
+        Ok(tokens)
+    }
+
+    fn relations(
+        &self,
+        basis: &CodeBasis,
+        sym_types: &BTreeMap<String, MVType>,
+    ) -> Result<Relations, String> {
+        let mut zeros = Vec::new();
+        for (name, assumption) in self.assumptions.iter() {
+            let arg = simplify_expr(basis, sym_types, &Expr::Symbol(name.clone()))?;
+            zeros.extend(match assumption {
+                Assumption::Unit => arg.unit_relations(&basis.basis)?,
+                Assumption::Null => arg.null_relations(&basis.basis)?,
+            });
+        }
+        Relations::new(&zeros).map_err(|e| format!("Argument assumptions: {}", e))
+    }
+
+    fn head_as_code(&self, basis: &CodeBasis) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        tokenstream_push(&mut tokens, Punct::new('|', Spacing::Alone).into());
+        tokens.extend(args_as_code(basis, &self.args));
+        tokenstream_push(&mut tokens, Punct::new('|', Spacing::Alone).into());
+        tokens
+    }
 }
 
 fn args_as_code(basis: &CodeBasis, args: &[(String, MVType)]) -> TokenStream {
@@ -64,7 +141,7 @@ fn arg_as_code(basis: &CodeBasis, name: &str, mv_type: &MVType) -> TokenStream {
             tokenstream_push(&mut pattern_tokens, Punct::new(',', Spacing::Alone).into());
         }
 
-        let term_name = format!("{}_{}", name, element_term_name(e));
+        let term_name = format!("{}_{}", name, element_term_name(basis, e));
         if e.0.is_empty() {
             tokenstream_push(
                 &mut pattern_tokens,
@@ -101,3 +178,122 @@ fn arg_as_code(basis: &CodeBasis, name: &str, mv_type: &MVType) -> TokenStream {
 
     tokens
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use symbolic_ga::basis::{Basis, Vector};
+
+    use super::*;
+    use crate::parse::lambda::parse_lambda;
+
+    fn g3() -> CodeBasis {
+        CodeBasis {
+            basis: Basis::new(0, 3, 0),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        }
+    }
+
+    fn lambda_src(src: &str) -> Result<String, String> {
+        let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+        let lambda = parse_lambda(&g3(), &mut tokens)?;
+        Ok(lambda.as_code(&g3())?.to_string())
+    }
+
+    fn jacobian_src(src: &str, wrt: &str) -> Result<String, String> {
+        let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+        let lambda = parse_lambda(&g3(), &mut tokens)?;
+        Ok(lambda.jacobian_as_code(&g3(), wrt)?.to_string())
+    }
+
+    #[test]
+    fn test_jacobian_keeps_result_type() -> Result<(), String> {
+        // d/dx (a a x) is a a for each component, padded with zeros to the result type
+        assert_eq!(
+            jacobian_src("|a: 1, x: e0 + e1| a * a * x", "x")?,
+            "| a_1 : f32 , (E0 (x_e0) , E1 (x_e1)) : (E0 , E1) , | \
+             ((E0 (a_1 * a_1) , E1 (0.0)) , (E0 (0.0) , E1 (a_1 * a_1)))"
+        );
+        assert_eq!(
+            jacobian_src("|a: 1, x: e0 + e1| a * a * x", "a")?,
+            "| a_1 : f32 , (E0 (x_e0) , E1 (x_e1)) : (E0 , E1) , | \
+             ((E0 (2.0 * a_1 * x_e0) , E1 (2.0 * a_1 * x_e1)))"
+        );
+        assert!(jacobian_src("|a: 1| a", "b").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_assumptions_reduce_result() -> Result<(), String> {
+        assert_eq!(
+            lambda_src("|r: 1 + e0e1 unit| r * ~r")?,
+            "| (r_1 , E0E1 (r_e0e1)) : (f32 , E0E1) , | (1.0)"
+        );
+        assert_eq!(
+            lambda_src("|n: e0 + e1 null, x: 1| x * n * n")?,
+            "| (E0 (n_e0) , E1 (n_e1)) : (E0 , E1) , x_1 : f32 , | ()"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_named_vectors() -> Result<(), String> {
+        let names = ["w", "x", "y"].iter().map(|name| name.to_string());
+        let basis = CodeBasis {
+            basis: Basis::from_signature(&[0, 1, 1])?.with_names(names.collect())?,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let mut tokens = TokenStream::from_str("|a: x + y, b: w| a * b + wxy")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        let lambda = parse_lambda(&basis, &mut tokens)?;
+        assert_eq!(
+            lambda.as_code(&basis)?.to_string(),
+            "| (X (a_x) , Y (a_y)) : (X , Y) , W (b_w) : W , | \
+             (WX (- a_x * b_w) , WXY (1.0) , WY (- a_y * b_w))"
+        );
+
+        // An argument named like a vector would be hidden by it in the body
+        let mut tokens = TokenStream::from_str("|x: x + y, a: x + y| x * a")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        assert!(parse_lambda(&basis, &mut tokens).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blade_orientations() -> Result<(), String> {
+        let digits = ["0", "1", "2", "3"].iter().map(|name| name.to_string());
+        let basis = CodeBasis {
+            basis: Basis::from_signature(&[0, 1, 1, 1])?
+                .with_names(digits.collect())?
+                .with_orientations(vec![vec![Vector(3), Vector(1)]])?,
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        };
+        let oriented_src = |src: &str| -> Result<String, String> {
+            let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+            let lambda = parse_lambda(&basis, &mut tokens)?;
+            Ok(lambda.as_code(&basis)?.to_string())
+        };
+
+        // e1 ^ e3 = -e31
+        assert_eq!(
+            oriented_src("|a: e1, b: e3| a ^ b")?,
+            "| E1 (a_e1) : E1 , E3 (b_e3) : E3 , | (E31 (- a_e1 * b_e3))"
+        );
+        // e31 e1 = e3
+        assert_eq!(
+            oriented_src("|a: e31| a * e1 + e31")?,
+            "| E31 (a_e31) : E31 , | (E31 (1.0) , E3 (a_e31))"
+        );
+        assert!(oriented_src("|a: e13| a").is_err());
+        assert!(oriented_src("|a: e21| a").is_err());
+        Ok(())
+    }
+}
@@ -1,18 +1,30 @@
 pub mod expr;
 pub mod lambda;
 pub mod parse;
+pub mod presets;
 pub mod structs;
+pub mod term;
 mod tokens;
 pub mod types;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use symbolic_ga::basis::{Basis, Grade, Vector};
 use symbolic_ga::element::Element;
+use symbolic_ga::function::Function;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MVType(pub BTreeSet<Element>);
 
+/// What a `ga!` argument is known to satisfy, written after its type like `r: 1 + e1e2 unit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assumption {
+    /// `x ~x = 1`, as for normalized rotors and motors.
+    Unit,
+    /// `x x = 0`, as for null vectors.
+    Null,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Brackets(Box<Expr>),
@@ -20,15 +32,33 @@ pub enum Expr {
     Symbol(String),
     Constant(isize),
     Negate(Box<Expr>),
+    Reverse(Box<Expr>),
+    Involute(Box<Expr>),
+    Conjugate(Box<Expr>),
+    Exp(Box<Expr>),
+    Log(Box<Expr>),
+    Pow(Box<Expr>, isize),
+    /// Conformal embedding of a Euclidean vector, see `Conformal::up`.
+    Up(Box<Expr>),
+    Down(Box<Expr>),
+    Apply(Function, Vec<Expr>),
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
     Mul(Box<Expr>, Box<Expr>),
+    Outer(Box<Expr>, Box<Expr>),
+    Regressive(Box<Expr>, Box<Expr>),
+    Commutator(Box<Expr>, Box<Expr>),
+    AntiCommutator(Box<Expr>, Box<Expr>),
+    Sandwich(Box<Expr>, Box<Expr>),
     Div(Box<Expr>, Box<Expr>),
     Grade(Box<Expr>, BTreeSet<Grade>),
 }
 
+/// The basis as the macros see it, with the scalar type and named types like `motor` which
+/// can stand for an element list in `ga!` arguments.
 #[derive(Debug, Clone)]
 pub struct CodeBasis {
     pub basis: Basis,
     pub scalar: String,
+    pub types: BTreeMap<String, MVType>,
 }
@@ -4,11 +4,12 @@ use std::str::FromStr;
 use proc_macro2::{Delimiter, TokenTree};
 
 use symbolic_ga::basis::Grade;
+use symbolic_ga::function::Function;
 
 use crate::parse::element::try_parse_element;
 use crate::parse::function;
 use crate::parse::Tokens;
-use crate::Expr;
+use crate::{CodeBasis, Expr};
 
 fn parse_constant(literal: String) -> Result<Expr, String> {
     let parsed =
@@ -16,11 +17,11 @@ fn parse_constant(literal: String) -> Result<Expr, String> {
     Ok(Expr::Constant(parsed))
 }
 
-fn parse_ident(tokens: &mut Tokens, name: String) -> Result<Expr, String> {
+fn parse_ident(basis: &CodeBasis, tokens: &mut Tokens, name: String) -> Result<Expr, String> {
     match name.as_str() {
         "grade" => {
-            let args = function::parse_args(tokens)?;
-            let body = args.get(0).ok_or("No body parsed to grade function")?;
+            let args = function::parse_args(basis, tokens)?;
+            let body = args.first().ok_or("No body parsed to grade function")?;
 
             let mut grades: BTreeSet<Grade> = BTreeSet::new();
             for grade_expr in &args[1..] {
@@ -39,43 +40,143 @@ fn parse_ident(tokens: &mut Tokens, name: String) -> Result<Expr, String> {
 
             Ok(Expr::Grade(Box::new(body.clone()), grades))
         }
-        _ => Ok(try_parse_element(&name)
+        "reverse" => parse_unary_function(basis, tokens, "reverse", Expr::Reverse),
+        "involute" => parse_unary_function(basis, tokens, "involute", Expr::Involute),
+        "conjugate" => parse_unary_function(basis, tokens, "conjugate", Expr::Conjugate),
+        "exp" => parse_unary_function(basis, tokens, "exp", Expr::Exp),
+        "log" => parse_unary_function(basis, tokens, "log", Expr::Log),
+        "up" => parse_unary_function(basis, tokens, "up", Expr::Up),
+        "down" => parse_unary_function(basis, tokens, "down", Expr::Down),
+        "pow" => match function::parse_args(basis, tokens)?.as_slice() {
+            [base, exponent] => Ok(Expr::Pow(Box::new(base.clone()), parse_exponent(exponent)?)),
+            args => Err(format!(
+                "pow function takes 2 arguments, given {}",
+                args.len()
+            )),
+        },
+        "commutator" => parse_binary_function(basis, tokens, "commutator", Expr::Commutator),
+        "anticommutator" => {
+            parse_binary_function(basis, tokens, "anticommutator", Expr::AntiCommutator)
+        }
+        "sandwich" => parse_binary_function(basis, tokens, "sandwich", Expr::Sandwich),
+        // Subspaces in plane-based PGA, see `MultiVector::meet`
+        "meet" => parse_binary_function(basis, tokens, "meet", Expr::Outer),
+        "join" => parse_binary_function(basis, tokens, "join", Expr::Regressive),
+        _ if Function::from_name(&name).is_some() => {
+            let function = Function::from_name(&name).expect("Checked function name");
+            let args = function::parse_args(basis, tokens)?;
+            if args.len() == function.arity() {
+                Ok(Expr::Apply(function, args))
+            } else {
+                Err(format!(
+                    "{} function takes {} arguments, given {}",
+                    name,
+                    function.arity(),
+                    args.len()
+                ))
+            }
+        }
+        _ => Ok(try_parse_element(&basis.basis, &name)
             .map(Expr::Element)
             .unwrap_or(Expr::Symbol(name))),
     }
 }
 
-pub fn parse_operand(tokens: &mut Tokens) -> Result<Expr, String> {
+/// Exponents are integer constants so the power can be expanded symbolically.
+fn parse_exponent(expr: &Expr) -> Result<isize, String> {
+    match expr {
+        Expr::Constant(n) => Ok(*n),
+        Expr::Negate(inner) => parse_exponent(inner).map(|n| -n),
+        Expr::Brackets(inner) => parse_exponent(inner),
+        expr => Err(format!(
+            "pow function requires a constant exponent, given '{:?}'",
+            expr
+        )),
+    }
+}
+
+fn parse_unary_function(
+    basis: &CodeBasis,
+    tokens: &mut Tokens,
+    name: &str,
+    constructor: fn(Box<Expr>) -> Expr,
+) -> Result<Expr, String> {
+    match function::parse_args(basis, tokens)?.as_slice() {
+        [arg] => Ok(constructor(Box::new(arg.clone()))),
+        args => Err(format!(
+            "{} function takes 1 argument, given {}",
+            name,
+            args.len()
+        )),
+    }
+}
+
+fn parse_binary_function(
+    basis: &CodeBasis,
+    tokens: &mut Tokens,
+    name: &str,
+    constructor: fn(Box<Expr>, Box<Expr>) -> Expr,
+) -> Result<Expr, String> {
+    match function::parse_args(basis, tokens)?.as_slice() {
+        [lhs, rhs] => Ok(constructor(Box::new(lhs.clone()), Box::new(rhs.clone()))),
+        args => Err(format!(
+            "{} function takes 2 arguments, given {}",
+            name,
+            args.len()
+        )),
+    }
+}
+
+pub fn parse_operand(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Expr, String> {
     let next_token = tokens.next().ok_or("Unexpected end of expression")?;
 
     match next_token {
         TokenTree::Literal(l) => parse_constant(l.to_string()),
-        TokenTree::Ident(i) => parse_ident(tokens, i.to_string()),
+        TokenTree::Ident(i) => parse_ident(basis, tokens, i.to_string()),
         TokenTree::Punct(p) if p.as_char() == '-' => {
-            let e = parse_operand(tokens)?;
+            let e = parse_operand(basis, tokens)?;
             Ok(Expr::Negate(Box::new(e)))
         }
+        TokenTree::Punct(p) if p.as_char() == '~' => {
+            let e = parse_operand(basis, tokens)?;
+            Ok(Expr::Reverse(Box::new(e)))
+        }
         TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
             let mut tokens = g.stream().into_iter().peekable();
-            Ok(Expr::Brackets(parse_expression(&mut tokens)?.into()))
+            Ok(Expr::Brackets(parse_expression(basis, &mut tokens)?.into()))
         }
         token => Err(format!("Unexpected token in operand '{}'", token)),
     }
 }
 
-pub fn parse_expression(tokens: &mut Tokens) -> Result<Expr, String> {
-    let lhs = parse_operand(tokens)?;
+pub fn parse_expression(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Expr, String> {
+    let lhs = parse_operand(basis, tokens)?;
 
     match tokens.peek() {
-        Some(TokenTree::Punct(p)) if p.as_char() == '+' => parse_add_sub(tokens, Expr::Add, lhs),
-        Some(TokenTree::Punct(p)) if p.as_char() == '-' => parse_add_sub(tokens, Expr::Sub, lhs),
-        Some(TokenTree::Punct(p)) if p.as_char() == '*' => parse_mul_div(tokens, Expr::Mul, lhs),
-        Some(TokenTree::Punct(p)) if p.as_char() == '/' => parse_mul_div(tokens, Expr::Div, lhs),
+        Some(TokenTree::Punct(p)) if p.as_char() == '+' => {
+            parse_add_sub(basis, tokens, Expr::Add, lhs)
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '-' => {
+            parse_add_sub(basis, tokens, Expr::Sub, lhs)
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '*' => {
+            parse_mul_div(basis, tokens, Expr::Mul, lhs)
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '/' => {
+            parse_mul_div(basis, tokens, Expr::Div, lhs)
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '^' => {
+            parse_outer_regressive(basis, tokens, Expr::Outer, lhs)
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '&' => {
+            parse_outer_regressive(basis, tokens, Expr::Regressive, lhs)
+        }
         _ => Ok(lhs),
     }
 }
 
 fn parse_add_sub(
+    basis: &CodeBasis,
     tokens: &mut Tokens,
     constructor: fn(Box<Expr>, Box<Expr>) -> Expr,
     lhs: Expr,
@@ -94,11 +195,12 @@ fn parse_add_sub(
         }
     }
 
-    let rhs = parse_expression(tokens)?;
+    let rhs = parse_expression(basis, tokens)?;
     Ok(add_left(constructor, lhs, rhs))
 }
 
 fn parse_mul_div(
+    basis: &CodeBasis,
     tokens: &mut Tokens,
     constructor: fn(Box<Expr>, Box<Expr>) -> Expr,
     lhs: Expr,
@@ -123,24 +225,70 @@ fn parse_mul_div(
         }
     }
 
-    let rhs = parse_expression(tokens)?;
+    let rhs = parse_expression(basis, tokens)?;
     Ok(mul_left(constructor, lhs, rhs))
 }
 
+/// `^` and `&` bind tighter than `*` and `/`, so `a * b ^ c` is `a * (b ^ c)`.
+fn parse_outer_regressive(
+    basis: &CodeBasis,
+    tokens: &mut Tokens,
+    constructor: fn(Box<Expr>, Box<Expr>) -> Expr,
+    lhs: Expr,
+) -> Result<Expr, String> {
+    tokens.next().expect("Expected to skip wedge symbol");
+
+    fn wedge_left(constructor: fn(Box<Expr>, Box<Expr>) -> Expr, lhs: Expr, e: Expr) -> Expr {
+        match e {
+            Expr::Add(e_lhs, e_rhs) => {
+                Expr::Add(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            Expr::Sub(e_lhs, e_rhs) => {
+                Expr::Sub(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            Expr::Mul(e_lhs, e_rhs) => {
+                Expr::Mul(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            Expr::Div(e_lhs, e_rhs) => {
+                Expr::Div(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            Expr::Outer(e_lhs, e_rhs) => {
+                Expr::Outer(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            Expr::Regressive(e_lhs, e_rhs) => {
+                Expr::Regressive(Box::new(wedge_left(constructor, lhs, *e_lhs)), e_rhs)
+            }
+            e => constructor(Box::new(lhs), Box::new(e)),
+        }
+    }
+
+    let rhs = parse_expression(basis, tokens)?;
+    Ok(wedge_left(constructor, lhs, rhs))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::str::FromStr;
 
     use proc_macro2::TokenStream;
 
-    use symbolic_ga::basis::Vector;
+    use symbolic_ga::basis::{Basis, Vector};
 
     use super::*;
 
+    fn g3() -> CodeBasis {
+        CodeBasis {
+            basis: Basis::new(0, 3, 0),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        }
+    }
+
     #[test]
     fn test_parse_single_eos() {
         let mut tokens = TokenStream::new().into_iter().peekable();
-        match parse_operand(&mut tokens) {
+        match parse_operand(&g3(), &mut tokens) {
             Err(_e) => {}
             Ok(_l) => panic!("Should have failed"),
         }
@@ -154,7 +302,7 @@ mod tests {
             .peekable();
 
         for expected in [1, 12, 123].iter() {
-            match parse_operand(&mut tokens)?.into() {
+            match parse_operand(&g3(), &mut tokens)? {
                 Expr::Constant(c) => {
                     assert_eq!(c, *expected);
                 }
@@ -173,7 +321,7 @@ mod tests {
             .peekable();
 
         for expected in ["你好", "World"].iter() {
-            match parse_operand(&mut tokens).unwrap().into() {
+            match parse_operand(&g3(), &mut tokens).unwrap() {
                 Expr::Symbol(s) => {
                     assert_eq!(s.as_str(), *expected);
                 }
@@ -189,10 +337,30 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_operand(&mut tokens).unwrap().into();
+        let e: Expr = parse_operand(&g3(), &mut tokens).unwrap();
         assert_eq!(e, Expr::Negate(Box::new(Expr::Constant(123))));
     }
 
+    #[test]
+    fn test_parse_reverse() {
+        let mut tokens = TokenStream::from_str("r * x * ~r")
+            .unwrap()
+            .into_iter()
+            .peekable();
+
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
+        assert_eq!(
+            e,
+            Expr::Mul(
+                Box::new(Expr::Mul(
+                    Box::new(Expr::Symbol("r".to_string())),
+                    Box::new(Expr::Symbol("x".to_string())),
+                )),
+                Box::new(Expr::Reverse(Box::new(Expr::Symbol("r".to_string())))),
+            )
+        );
+    }
+
     #[test]
     fn test_parse_base_elements() {
         let examples = [
@@ -206,7 +374,7 @@ mod tests {
 
         for (src, expected) in examples.iter() {
             let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
-            let e: Expr = parse_expression(&mut tokens).unwrap().into();
+            let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
             assert_eq!(&e, expected);
         }
     }
@@ -218,7 +386,7 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_expression(&mut tokens).unwrap().into();
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
         assert_eq!(
             e,
             Expr::Add(
@@ -238,7 +406,7 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_expression(&mut tokens).unwrap().into();
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
         assert_eq!(
             e,
             Expr::Add(
@@ -258,7 +426,7 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_expression(&mut tokens).unwrap().into();
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
         assert_eq!(
             e,
             Expr::Sub(
@@ -318,7 +486,7 @@ mod tests {
 
         for (src, expected) in examples.iter() {
             let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
-            let e: Expr = parse_expression(&mut tokens).unwrap().into();
+            let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
             assert_eq!(&e, expected);
         }
     }
@@ -330,7 +498,7 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_expression(&mut tokens).unwrap().into();
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
         assert_eq!(
             e,
             Expr::Div(
@@ -343,6 +511,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_outer_product() {
+        let symbol = |name: &str| Box::new(Expr::Symbol(name.to_string()));
+        let examples = [
+            (
+                "a * b ^ c + d",
+                Expr::Add(
+                    Box::new(Expr::Mul(
+                        symbol("a"),
+                        Box::new(Expr::Outer(symbol("b"), symbol("c"))),
+                    )),
+                    symbol("d"),
+                ),
+            ),
+            (
+                "a ^ b / c ^ d",
+                Expr::Div(
+                    Box::new(Expr::Outer(symbol("a"), symbol("b"))),
+                    Box::new(Expr::Outer(symbol("c"), symbol("d"))),
+                ),
+            ),
+            (
+                "a * b & c * d",
+                Expr::Mul(
+                    Box::new(Expr::Mul(
+                        symbol("a"),
+                        Box::new(Expr::Regressive(symbol("b"), symbol("c"))),
+                    )),
+                    symbol("d"),
+                ),
+            ),
+        ];
+
+        for (src, expected) in examples.iter() {
+            let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+            let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
+            assert_eq!(&e, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_regressive_and_meet() {
+        let examples = [
+            (
+                "a & b ^ c",
+                Expr::Outer(
+                    Box::new(Expr::Regressive(
+                        Box::new(Expr::Symbol("a".to_string())),
+                        Box::new(Expr::Symbol("b".to_string())),
+                    )),
+                    Box::new(Expr::Symbol("c".to_string())),
+                ),
+            ),
+            (
+                "meet(a, join(b, c))",
+                Expr::Outer(
+                    Box::new(Expr::Symbol("a".to_string())),
+                    Box::new(Expr::Regressive(
+                        Box::new(Expr::Symbol("b".to_string())),
+                        Box::new(Expr::Symbol("c".to_string())),
+                    )),
+                ),
+            ),
+        ];
+
+        for (src, expected) in examples.iter() {
+            let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+            let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
+            assert_eq!(&e, expected);
+        }
+
+        let mut tokens = TokenStream::from_str("meet(a)")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        assert!(parse_expression(&g3(), &mut tokens).is_err());
+    }
+
     #[test]
     fn test_parse_grade_function() {
         let mut tokens = TokenStream::from_str("grade(e1 + e1e2, 2)")
@@ -350,7 +596,7 @@ mod tests {
             .into_iter()
             .peekable();
 
-        let e: Expr = parse_expression(&mut tokens).unwrap().into();
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
         assert_eq!(
             e,
             Expr::Grade(
@@ -362,4 +608,27 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_parse_pow_function() {
+        let mut tokens = TokenStream::from_str("pow(r, -3) * x")
+            .unwrap()
+            .into_iter()
+            .peekable();
+
+        let e: Expr = parse_expression(&g3(), &mut tokens).unwrap();
+        assert_eq!(
+            e,
+            Expr::Mul(
+                Box::new(Expr::Pow(Box::new(Expr::Symbol("r".to_string())), -3)),
+                Box::new(Expr::Symbol("x".to_string())),
+            )
+        );
+
+        let mut tokens = TokenStream::from_str("pow(r, n)")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        assert!(parse_expression(&g3(), &mut tokens).is_err());
+    }
 }
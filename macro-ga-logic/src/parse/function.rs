@@ -2,16 +2,16 @@ use proc_macro2::TokenTree;
 
 use crate::parse::expr::parse_expression;
 use crate::parse::Tokens;
-use crate::Expr;
+use crate::{CodeBasis, Expr};
 
-pub fn parse_args(tokens: &mut Tokens) -> Result<Vec<Expr>, String> {
+pub fn parse_args(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Vec<Expr>, String> {
     let mut exprs = Vec::new();
 
     match tokens.next() {
         Some(TokenTree::Group(g)) => {
             let mut tokens = g.stream().into_iter().peekable();
             loop {
-                exprs.push(parse_expression(&mut tokens)?);
+                exprs.push(parse_expression(basis, &mut tokens)?);
                 match tokens.next() {
                     None => return Ok(exprs),
                     Some(TokenTree::Punct(p)) if p.as_char() == ',' => continue,
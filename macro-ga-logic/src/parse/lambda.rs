@@ -1,12 +1,14 @@
 use proc_macro2::TokenTree;
 
+use symbolic_ga::parse::try_parse_element;
+
 use crate::lambda::Lambda;
 use crate::parse::expr::parse_expression;
 use crate::parse::mvtype::parse_type;
 use crate::parse::Tokens;
-use crate::MVType;
+use crate::{Assumption, CodeBasis, MVType};
 
-pub fn parse_lambda(tokens: &mut Tokens) -> Result<Lambda, String> {
+pub fn parse_lambda(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Lambda, String> {
     match tokens.next() {
         Some(TokenTree::Punct(p)) if p.as_char() == '|' => {}
         token => {
@@ -17,13 +19,24 @@ pub fn parse_lambda(tokens: &mut Tokens) -> Result<Lambda, String> {
         }
     };
 
-    let args = parse_args(tokens)?;
-    let body = parse_expression(tokens)?;
+    let args = parse_args(basis, tokens)?;
+    let body = parse_expression(basis, tokens)?;
+
+    let assumptions = args
+        .iter()
+        .filter_map(|(id, _mv_type, assumption)| assumption.map(|a| (id.clone(), a)))
+        .collect();
+    let args = args
+        .into_iter()
+        .map(|(id, mv_type, _assumption)| (id, mv_type))
+        .collect();
 
-    Lambda::new(args, body)
+    Ok(Lambda::new(args, body)?.with_assumptions(assumptions))
 }
 
-fn parse_args(tokens: &mut Tokens) -> Result<Vec<(String, MVType)>, String> {
+type Arg = (String, MVType, Option<Assumption>);
+
+fn parse_args(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Vec<Arg>, String> {
     let id = match tokens.next() {
         Some(TokenTree::Ident(i)) => i.to_string(),
         token => {
@@ -33,6 +46,10 @@ fn parse_args(tokens: &mut Tokens) -> Result<Vec<(String, MVType)>, String> {
             return Err(format!("Expected arg name, got: {}", token));
         }
     };
+    // The body would read the name as the element
+    if try_parse_element(&basis.basis, &id).is_some() {
+        return Err(format!("Arg name is a basis element: {}", id));
+    }
 
     match tokens.next() {
         Some(TokenTree::Punct(p)) if p.as_char() == ':' => {}
@@ -44,9 +61,10 @@ fn parse_args(tokens: &mut Tokens) -> Result<Vec<(String, MVType)>, String> {
         }
     };
 
-    let mv_type = parse_type(tokens)?;
+    let mv_type = parse_type(basis, tokens)?;
+    let assumption = parse_assumption(tokens)?;
 
-    let arg = (id, mv_type);
+    let arg = (id, mv_type, assumption);
 
     match tokens.peek() {
         Some(TokenTree::Punct(p)) if p.as_char() == '|' => {
@@ -55,7 +73,7 @@ fn parse_args(tokens: &mut Tokens) -> Result<Vec<(String, MVType)>, String> {
         }
         Some(TokenTree::Punct(p)) if p.as_char() == ',' => {
             tokens.next().expect("Peeked comma");
-            parse_args(tokens).map(|rest| std::iter::once(arg).chain(rest.into_iter()).collect())
+            parse_args(basis, tokens).map(|rest| std::iter::once(arg).chain(rest).collect())
         }
         token => {
             let token = token
@@ -66,16 +84,35 @@ fn parse_args(tokens: &mut Tokens) -> Result<Vec<(String, MVType)>, String> {
     }
 }
 
+fn parse_assumption(tokens: &mut Tokens) -> Result<Option<Assumption>, String> {
+    let assumption = match tokens.peek() {
+        Some(TokenTree::Ident(i)) if i == "unit" => Assumption::Unit,
+        Some(TokenTree::Ident(i)) if i == "null" => Assumption::Null,
+        Some(TokenTree::Ident(i)) => return Err(format!("Unknown assumption: {}", i)),
+        _ => return Ok(None),
+    };
+    tokens.next().expect("Peeked assumption");
+    Ok(Some(assumption))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::str::FromStr;
 
     use proc_macro2::TokenStream;
 
-    use symbolic_ga::basis::Vector;
+    use symbolic_ga::basis::{Basis, Vector};
 
     use super::*;
+
+    fn g3() -> CodeBasis {
+        CodeBasis {
+            basis: Basis::new(0, 3, 0),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        }
+    }
     use crate::{Element, Expr};
 
     #[test]
@@ -115,9 +152,34 @@ mod tests {
             Box::new(Expr::Symbol("b".to_string())),
         );
 
-        let f = parse_lambda(&mut tokens).unwrap();
+        let f = parse_lambda(&g3(), &mut tokens).unwrap();
 
         assert_eq!(f.args(), &expected_args);
         assert_eq!(f.body(), &expected_body);
+        assert!(f.assumptions().is_empty());
+    }
+
+    #[test]
+    fn test_parse_assumptions() {
+        let mut tokens = TokenStream::from_str("|r: 1 + e1e2 unit, n: e1 + e2 null, x: e1| r")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        let f = parse_lambda(&g3(), &mut tokens).unwrap();
+
+        assert_eq!(f.args().len(), 3);
+        assert_eq!(
+            f.assumptions(),
+            &vec![
+                ("r".to_string(), Assumption::Unit),
+                ("n".to_string(), Assumption::Null)
+            ]
+        );
+
+        let mut tokens = TokenStream::from_str("|r: 1 + e1e2 small| r")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        assert!(parse_lambda(&g3(), &mut tokens).is_err());
     }
 }
@@ -4,9 +4,9 @@ use proc_macro2::TokenTree;
 
 use crate::parse::element::try_parse_element;
 use crate::parse::Tokens;
-use crate::{Element, MVType};
+use crate::{CodeBasis, Element, MVType};
 
-pub fn parse_element(tokens: &mut Tokens) -> Result<Element, String> {
+pub fn parse_element(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Element, String> {
     let token = tokens
         .next()
         .ok_or_else(|| "Expected another element in type".to_string())?;
@@ -14,39 +14,34 @@ pub fn parse_element(tokens: &mut Tokens) -> Result<Element, String> {
     match token {
         TokenTree::Literal(l) if &l.to_string() == "1" => Ok(Element(BTreeSet::new())),
         TokenTree::Ident(i) => {
-            let element_name = try_parse_element(&i.to_string())
+            let element_name = try_parse_element(&basis.basis, &i.to_string())
                 .ok_or(format!("While parsing element name, got '{}'", i))?;
 
-            let ordered = element_name.windows(2).all(|els| match els {
-                [left, right] => left < right,
-                _ => unreachable!("Window size 2"),
-            });
+            let elem = Element(element_name.iter().cloned().collect());
 
-            if ordered {
-                Ok(Element(element_name.into_iter().collect()))
+            // Ordered, or as the basis orients the blade, so `e13` is not read as `-e31`
+            if basis.basis.orientation(&elem).0 == element_name {
+                Ok(elem)
             } else {
                 Err(format!(
-                    "Element vectors must be unique and ordered: '{}'",
+                    "Element vectors must be unique and ordered as the blade orientation: '{}'",
                     i
                 ))
             }
         }
-        token => Err(format!(
-            "Expected an element name, got '{}'",
-            token.to_string()
-        )),
+        token => Err(format!("Expected an element name, got '{}'", token)),
     }
 }
 
-pub fn parse_element_list(tokens: &mut Tokens) -> Result<Vec<Element>, String> {
-    let mut elems = vec![parse_element(tokens)?];
+pub fn parse_element_list(basis: &CodeBasis, tokens: &mut Tokens) -> Result<Vec<Element>, String> {
+    let mut elems = vec![parse_element(basis, tokens)?];
 
     loop {
         let next = tokens.peek();
         match next {
             Some(TokenTree::Punct(p)) if p.as_char() == '+' => {
                 tokens.next().expect("Just peeked a plus token");
-                elems.push(parse_element(tokens)?);
+                elems.push(parse_element(basis, tokens)?);
             }
             _ => break,
         }
@@ -55,8 +50,16 @@ pub fn parse_element_list(tokens: &mut Tokens) -> Result<Vec<Element>, String> {
     Ok(elems)
 }
 
-pub fn parse_type(tokens: &mut Tokens) -> Result<MVType, String> {
-    let elems = parse_element_list(tokens)?;
+/// An element list like `1 + e1e2`, or a name from the basis like `rotor`.
+pub fn parse_type(basis: &CodeBasis, tokens: &mut Tokens) -> Result<MVType, String> {
+    if let Some(TokenTree::Ident(i)) = tokens.peek() {
+        if let Some(mv_type) = basis.types.get(&i.to_string()) {
+            tokens.next().expect("Peeked type name");
+            return Ok(mv_type.clone());
+        }
+    }
+
+    let elems = parse_element_list(basis, tokens)?;
 
     let ordered = elems.windows(2).all(|els| match els {
         [left, right] => left < right,
@@ -66,7 +69,9 @@ pub fn parse_type(tokens: &mut Tokens) -> Result<MVType, String> {
     if ordered {
         Ok(MVType(elems.into_iter().collect()))
     } else {
-        let correct_order = elems.clone().sort();
+        let mut correct_order = elems.clone();
+        correct_order.sort();
+        correct_order.dedup();
 
         Err(format!(
             "Elements in type must be in order and unique: '{:?}' => '{:?}'",
@@ -77,14 +82,23 @@ pub fn parse_type(tokens: &mut Tokens) -> Result<MVType, String> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::str::FromStr;
 
     use proc_macro2::TokenStream;
 
-    use symbolic_ga::basis::Vector;
+    use symbolic_ga::basis::{Basis, Vector};
 
     use super::*;
 
+    fn g3() -> CodeBasis {
+        CodeBasis {
+            basis: Basis::new(0, 3, 0),
+            scalar: "f32".to_string(),
+            types: BTreeMap::new(),
+        }
+    }
+
     #[test]
     fn test_parse_element_list() {
         let mut tokens = TokenStream::from_str("1 + e1 + e1e2e3|")
@@ -93,7 +107,7 @@ mod tests {
             .peekable();
 
         assert_eq!(
-            parse_element_list(&mut tokens).unwrap(),
+            parse_element_list(&g3(), &mut tokens).unwrap(),
             vec![
                 Element(BTreeSet::new()),
                 Element(vec![1].into_iter().map(Vector).collect()),
@@ -108,7 +122,7 @@ mod tests {
             .unwrap()
             .into_iter()
             .peekable();
-        assert!(parse_element_list(&mut tokens).is_err())
+        assert!(parse_element_list(&g3(), &mut tokens).is_err())
     }
 
     #[test]
@@ -117,6 +131,27 @@ mod tests {
             .unwrap()
             .into_iter()
             .peekable();
-        assert!(parse_element_list(&mut tokens).is_err())
+        assert!(parse_element_list(&g3(), &mut tokens).is_err())
+    }
+
+    #[test]
+    fn test_parse_named_type() -> Result<(), String> {
+        let mut basis = g3();
+        let rotor = parse_type(
+            &basis,
+            &mut TokenStream::from_str("1 + e1e2")
+                .unwrap()
+                .into_iter()
+                .peekable(),
+        )?;
+        basis.types.insert("rotor".to_string(), rotor.clone());
+
+        let mut tokens = TokenStream::from_str("rotor, e1")
+            .unwrap()
+            .into_iter()
+            .peekable();
+        assert_eq!(parse_type(&basis, &mut tokens)?, rotor);
+        assert_eq!(tokens.next().map(|t| t.to_string()), Some(",".to_string()));
+        Ok(())
     }
 }
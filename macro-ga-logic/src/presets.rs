@@ -0,0 +1,313 @@
+//! Standard algebras for `define_basis!(PGA3, f32, PGA3D)`. Vectors are numbered like the
+//! published tables, blades oriented as there, and the usual multivector types are named.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use symbolic_ga::basis::{Basis, Vector};
+use symbolic_ga::conformal::Conformal;
+use symbolic_ga::element::Element;
+use symbolic_ga::parse::try_parse_element;
+
+use crate::{CodeBasis, MVType};
+
+struct Preset {
+    name: &'static str,
+    signature: &'static [isize],
+    conformal: bool,
+    vectors: &'static [&'static str],
+    orientations: &'static [&'static str],
+    blades: &'static [(&'static str, &'static str)],
+    types: &'static [(&'static str, &'static [&'static str])],
+}
+
+const PRESETS: &[Preset] = &[
+    Preset {
+        name: "G2",
+        signature: &[1, 1],
+        conformal: false,
+        vectors: &["1", "2"],
+        orientations: &[],
+        blades: &[],
+        types: &[("vector", &["e1", "e2"]), ("rotor", &["1", "e12"])],
+    },
+    Preset {
+        name: "G3",
+        signature: &[1, 1, 1],
+        conformal: false,
+        vectors: &["1", "2", "3"],
+        orientations: &["e31"],
+        blades: &[],
+        types: &[
+            ("vector", &["e1", "e2", "e3"]),
+            ("bivector", &["e12", "e31", "e23"]),
+            ("rotor", &["1", "e12", "e31", "e23"]),
+        ],
+    },
+    Preset {
+        name: "PGA2D",
+        signature: &[0, 1, 1],
+        conformal: false,
+        vectors: &["0", "1", "2"],
+        orientations: &["e20"],
+        blades: &[],
+        types: &[
+            ("line", &["e0", "e1", "e2"]),
+            ("point", &["e01", "e20", "e12"]),
+            ("rotor", &["1", "e12"]),
+            ("translator", &["1", "e01", "e20"]),
+            ("motor", &["1", "e01", "e20", "e12"]),
+        ],
+    },
+    Preset {
+        name: "PGA3D",
+        signature: &[0, 1, 1, 1],
+        conformal: false,
+        vectors: &["0", "1", "2", "3"],
+        orientations: &["e31", "e021", "e032"],
+        blades: &[],
+        types: &[
+            ("plane", &["e0", "e1", "e2", "e3"]),
+            ("line", &["e01", "e02", "e03", "e12", "e31", "e23"]),
+            ("point", &["e021", "e013", "e032", "e123"]),
+            ("rotor", &["1", "e12", "e31", "e23"]),
+            ("translator", &["1", "e01", "e02", "e03"]),
+            (
+                "motor",
+                &["1", "e01", "e02", "e03", "e12", "e31", "e23", "e0123"],
+            ),
+        ],
+    },
+    // Conformal with e+ then e- after the Euclidean vectors, see `Conformal`
+    Preset {
+        name: "CGA2D",
+        signature: &[1, 1, 1, -1],
+        conformal: true,
+        vectors: &["1", "2", "3", "4"],
+        orientations: &[],
+        blades: &[],
+        types: &[
+            ("point", &["e1", "e2", "e3", "e4"]),
+            ("line", &["e1", "e2", "e3", "e4"]),
+            ("circle", &["e1", "e2", "e3", "e4"]),
+            ("rotor", &["1", "e12"]),
+            ("translator", &["1", "e13", "e14", "e23", "e24"]),
+            ("motor", &["1", "e12", "e13", "e14", "e23", "e24"]),
+        ],
+    },
+    Preset {
+        name: "CGA3D",
+        signature: &[1, 1, 1, 1, -1],
+        conformal: true,
+        vectors: &["1", "2", "3", "4", "5"],
+        orientations: &[],
+        blades: &[],
+        types: &[
+            ("point", &["e1", "e2", "e3", "e4", "e5"]),
+            ("sphere", &["e1", "e2", "e3", "e4", "e5"]),
+            ("plane", &["e1", "e2", "e3", "e4", "e5"]),
+            (
+                "line",
+                &[
+                    "e12", "e13", "e23", "e14", "e15", "e24", "e25", "e34", "e35",
+                ],
+            ),
+            ("rotor", &["1", "e12", "e13", "e23"]),
+            (
+                "translator",
+                &["1", "e14", "e15", "e24", "e25", "e34", "e35"],
+            ),
+            (
+                "motor",
+                &[
+                    "1", "e12", "e13", "e23", "e14", "e15", "e24", "e25", "e34", "e35", "e1234",
+                    "e1235",
+                ],
+            ),
+        ],
+    },
+    // Spacetime algebra with a timelike e0
+    Preset {
+        name: "STA",
+        signature: &[1, -1, -1, -1],
+        conformal: false,
+        vectors: &["0", "1", "2", "3"],
+        orientations: &[],
+        blades: &[],
+        types: &[
+            ("vector", &["e0", "e1", "e2", "e3"]),
+            ("bivector", &["e01", "e02", "e03", "e12", "e13", "e23"]),
+            (
+                "rotor",
+                &["1", "e01", "e02", "e03", "e12", "e13", "e23", "e0123"],
+            ),
+        ],
+    },
+    Preset {
+        name: "COMPLEX",
+        signature: &[-1],
+        conformal: false,
+        vectors: &["i"],
+        orientations: &[],
+        blades: &[],
+        types: &[("complex", &["1", "i"])],
+    },
+    // The even subalgebra of G3, with ij = k, jk = i and ki = j
+    Preset {
+        name: "QUATERNION",
+        signature: &[1, 1, 1],
+        conformal: false,
+        vectors: &["1", "2", "3"],
+        orientations: &[],
+        blades: &[("i", "e12"), ("j", "e13"), ("k", "e32")],
+        types: &[("quaternion", &["1", "i", "j", "k"])],
+    },
+];
+
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|preset| preset.name).collect()
+}
+
+pub fn preset(name: &str, scalar: &str) -> Result<CodeBasis, String> {
+    let preset = PRESETS
+        .iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| {
+            format!(
+                "Unknown preset '{}', expected one of {:?}",
+                name,
+                preset_names()
+            )
+        })?;
+
+    let names = preset.vectors.iter().map(|v| v.to_string()).collect();
+    let basis = match preset.conformal {
+        true => Conformal::new(preset.signature.len() - 2).basis(),
+        false => Basis::from_signature(preset.signature)?,
+    };
+    let basis = basis.with_names(names)?;
+    let blades = preset
+        .orientations
+        .iter()
+        .map(|blade| parse_blade(&basis, blade))
+        .collect::<Result<_, String>>()?;
+    let basis = basis.with_orientations(blades)?;
+    let blade_names = preset
+        .blades
+        .iter()
+        .map(|(blade_name, blade)| Ok((parse_blade(&basis, blade)?, blade_name.to_string())))
+        .collect::<Result<_, String>>()?;
+    let basis = basis.with_blade_names(blade_names)?;
+
+    let mut types = BTreeMap::new();
+    for (type_name, elems) in preset.types.iter() {
+        let elems = elems
+            .iter()
+            .map(|elem| match *elem {
+                "1" => Ok(Element(BTreeSet::new())),
+                elem => parse_blade(&basis, elem).map(|vs| Element(vs.into_iter().collect())),
+            })
+            .collect::<Result<_, String>>()?;
+        types.insert(type_name.to_string(), MVType(elems));
+    }
+
+    Ok(CodeBasis {
+        basis,
+        scalar: scalar.to_string(),
+        types,
+    })
+}
+
+fn parse_blade(basis: &Basis, name: &str) -> Result<Vec<Vector>, String> {
+    try_parse_element(basis, name).ok_or_else(|| format!("Could not parse blade '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use proc_macro2::TokenStream;
+
+    use super::*;
+    use crate::parse::lambda::parse_lambda;
+
+    fn preset_src(preset_name: &str, src: &str) -> Result<String, String> {
+        let basis = preset(preset_name, "f32")?;
+        let mut tokens = TokenStream::from_str(src).unwrap().into_iter().peekable();
+        let lambda = parse_lambda(&basis, &mut tokens)?;
+        Ok(lambda.as_code(&basis)?.to_string())
+    }
+
+    #[test]
+    fn test_presets_are_consistent() -> Result<(), String> {
+        for name in preset_names() {
+            let basis = preset(name, "f32")?;
+            for (type_name, mv_type) in basis.types.iter() {
+                assert!(!mv_type.0.is_empty(), "{} {}", name, type_name);
+            }
+        }
+        assert!(preset("PGA4D", "f32").is_err());
+
+        let cga = preset("CGA3D", "f32")?;
+        assert_eq!(Conformal::from_basis(&cga.basis), Some(Conformal::new(3)));
+        assert_eq!(Conformal::from_basis(&preset("STA", "f32")?.basis), None);
+
+        // `define_basis!(P, f32, PGA3D, [e13])` reorients one blade and keeps the others
+        let pga3 = preset("PGA3D", "f32")?.basis;
+        let e13 = parse_blade(&pga3, "e13")?;
+        let pga3 = pga3.with_orientations(vec![e13])?;
+        for name in ["e13", "e021", "e032"].iter() {
+            let elem = try_parse_element(&pga3, name).ok_or("Not an element")?;
+            assert_eq!(
+                pga3.element_name(&Element(elem.into_iter().collect())),
+                *name
+            );
+        }
+        let quaternion = preset("QUATERNION", "f32")?.basis;
+        let e23 = parse_blade(&quaternion, "e23")?;
+        assert!(quaternion.with_orientations(vec![e23]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_preset_names_and_types() -> Result<(), String> {
+        // Two planes meet in a line, written with the published blade names
+        assert_eq!(
+            preset_src("PGA3D", "|a: e1, b: e3| a ^ b")?,
+            "| E1 (a_e1) : E1 , E3 (b_e3) : E3 , | (E31 (- a_e1 * b_e3))"
+        );
+        assert_eq!(
+            preset_src("PGA3D", "|a: plane, b: plane| meet(a, b)")?,
+            preset_src("PGA3D", "|a: plane, b: plane| a ^ b")?
+        );
+        assert_eq!(
+            preset_src("PGA3D", "|a: point, b: point| join(a, b)")?,
+            preset_src("PGA3D", "|a: point, b: point| a & b")?
+        );
+        // A motor moves a point to a point
+        let code = preset_src("PGA3D", "|m: motor, p: point| sandwich(m, p)")?;
+        assert!(code.contains(": (f32 , E01 , E0123 , E02 , E03 , E12 , E31 , E23) , "));
+        let (_head, body) = code.split_at(code.rfind(", |").expect("Closure head"));
+        for elem in ["E021 (", "E013 (", "E032 (", "E123 ("].iter() {
+            assert!(body.contains(elem));
+        }
+        assert!(!body.contains("E01 ("));
+        assert_eq!(
+            preset_src("QUATERNION", "|a: quaternion| a")?,
+            "| (a_1 , I (a_i) , J (a_j) , K (a_k)) : (f32 , I , J , K) , | \
+             (a_1 , I (a_i) , J (a_j) , K (a_k))"
+        );
+        for (src, expected) in [
+            ("i * j", "K (1.0)"),
+            ("j * k", "I (1.0)"),
+            ("k * i", "J (1.0)"),
+        ]
+        .iter()
+        {
+            let code = preset_src("QUATERNION", &format!("|a: quaternion| {}", src))?;
+            assert!(code.ends_with(&format!(", | ({})", expected)), "{}", code);
+        }
+        let code = preset_src("QUATERNION", "|a: quaternion| i * j * k")?;
+        assert!(code.ends_with(", | (- 1.0)"), "{}", code);
+        Ok(())
+    }
+}
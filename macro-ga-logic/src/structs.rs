@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use proc_macro2::TokenStream;
 
-use crate::types::element_type_name;
+use crate::types::{element_type_name, type_signiture};
 use crate::CodeBasis;
 
 pub fn generate_types(basis: &CodeBasis) -> TokenStream {
@@ -26,5 +26,18 @@ pub fn generate_types(basis: &CodeBasis) -> TokenStream {
         }
     }
 
+    // Named types become aliases like `type Rotor = (f32, E12);`
+    for (name, mv_type) in basis.types.iter() {
+        let mut chars = name.chars();
+        let alias: String = chars
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .into_iter()
+            .chain(chars)
+            .collect();
+        let src = format!("type {} = {};", alias, type_signiture(basis, mv_type));
+        tokens.extend(TokenStream::from_str(&src).expect("Creating type alias"));
+    }
+
     tokens
 }
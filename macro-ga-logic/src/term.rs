@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use num::rational::BigRational;
+use num::{Integer, One, Signed, Zero};
+
+use symbolic_ga::function::Function;
+use symbolic_ga::polynomial::{Polynomial, Symbol};
+use symbolic_ga::symbols::{lift_integer, SymbolPowers, Symbols};
+
+/// A scalar expression in the shape code is generated for. Polynomials are written as
+/// multivariate Horner schemes with signs pulled to the top, so equal subterms in different
+/// components compare equal and can be shared.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Term {
+    /// A nonnegative constant.
+    Constant(BigRational),
+    Variable(String),
+    Call(Function, Vec<Term>),
+    /// `x * x * ...`, at least squared.
+    Power(Box<Term>, usize),
+    Product(Vec<Term>),
+    /// Summands flagged when subtracted, the first is never subtracted.
+    Sum(Vec<(bool, Term)>),
+    Quotient(Box<Term>, Box<Term>),
+    Negate(Box<Term>),
+}
+
+impl Term {
+    pub fn from_polynomial(poly: &Polynomial) -> Term {
+        match signed_polynomial(poly) {
+            (true, term) => Term::Negate(Box::new(term)),
+            (false, term) => term,
+        }
+    }
+
+    /// A rational function written out as a quotient, for places that cannot share reciprocals.
+    pub fn from_symbols(syms: &Symbols) -> Term {
+        if syms.is_polynomial() {
+            Term::from_polynomial(&syms.numer)
+        } else {
+            Term::Quotient(
+                Box::new(Term::from_polynomial(&syms.numer)),
+                Box::new(Term::from_polynomial(&syms.denom)),
+            )
+        }
+    }
+
+    /// The subterms, repeated as often as they are written out.
+    fn children(&self) -> Vec<&Term> {
+        match self {
+            Term::Constant(_) | Term::Variable(_) => Vec::new(),
+            Term::Call(_function, args) => args.iter().collect(),
+            Term::Power(base, pow) => std::iter::repeat_n(base.as_ref(), *pow).collect(),
+            Term::Product(factors) => factors.iter().collect(),
+            Term::Sum(summands) => summands.iter().map(|(_negative, term)| term).collect(),
+            Term::Quotient(numer, denom) => vec![numer.as_ref(), denom.as_ref()],
+            Term::Negate(term) => vec![term.as_ref()],
+        }
+    }
+}
+
+/// The terms written out more than once, each bound to a variable before it is first used.
+#[derive(Debug, Default)]
+pub struct SharedTerms {
+    order: Vec<Term>,
+    index: BTreeMap<Term, usize>,
+}
+
+impl SharedTerms {
+    pub fn new<'a>(terms: impl IntoIterator<Item = &'a Term> + Clone) -> SharedTerms {
+        let mut counts = BTreeMap::new();
+        for term in terms.clone() {
+            count_terms(term, &mut counts);
+        }
+
+        let mut shared = SharedTerms::default();
+        for term in terms {
+            shared.bind(term, &counts);
+        }
+        shared
+    }
+
+    /// Shared terms in an order where each only uses earlier ones.
+    pub fn terms(&self) -> &[Term] {
+        &self.order
+    }
+
+    pub fn index(&self, term: &Term) -> Option<usize> {
+        self.index.get(term).cloned()
+    }
+
+    fn bind(&mut self, term: &Term, counts: &BTreeMap<Term, usize>) {
+        if self.index.contains_key(term) {
+            return;
+        }
+        for child in term.children() {
+            self.bind(child, counts);
+        }
+        if counts.get(term).is_some_and(|count| *count > 1) {
+            self.index.insert(term.clone(), self.order.len());
+            self.order.push(term.clone());
+        }
+    }
+}
+
+/// Counts composite terms, only looking inside the first occurrence of each so that parts of
+/// a repeated term are not counted again.
+fn count_terms(term: &Term, counts: &mut BTreeMap<Term, usize>) {
+    if let Term::Constant(_) | Term::Variable(_) = term {
+        return;
+    }
+    let count = counts.entry(term.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        for child in term.children() {
+            count_terms(child, counts);
+        }
+    }
+}
+
+/// The polynomial as a sign and a term, where the leading summand of every sum is positive.
+fn signed_polynomial(poly: &Polynomial) -> (bool, Term) {
+    let mut terms = poly.0.iter();
+    let (powers, scale) = match (terms.next(), terms.next()) {
+        (None, _) => return (false, Term::Constant(lift_integer(0))),
+        (Some((powers, scale)), None) => (powers, scale),
+        _ => return signed_sum(poly),
+    };
+
+    let mut factors = Vec::new();
+    if !scale.abs().is_one() || powers.is_empty() {
+        factors.push(Term::Constant(scale.abs()));
+    }
+    for (sym, pow) in powers.iter() {
+        let sym = symbol_term(sym);
+        factors.push(match pow {
+            1 => sym,
+            pow => Term::Power(Box::new(sym), *pow),
+        });
+    }
+
+    (scale.is_negative(), product(factors))
+}
+
+/// Pulls out common rational factors, then the symbol in the most terms, recursing on both the
+/// quotient and the remainder.
+fn signed_sum(poly: &Polynomial) -> (bool, Term) {
+    let content = rational_content(poly);
+    if !content.is_one() {
+        let (negative, term) = signed_polynomial(&poly.scale(&content.recip()));
+        return (negative, product(vec![Term::Constant(content), term]));
+    }
+
+    let mut occurrences: BTreeMap<&Symbol, usize> = BTreeMap::new();
+    for powers in poly.0.keys() {
+        for sym in powers.keys() {
+            *occurrences.entry(sym).or_insert(0) += 1;
+        }
+    }
+    let factor = occurrences
+        .iter()
+        .fold(
+            None,
+            |best: Option<(&Symbol, usize)>, (sym, count)| match best {
+                Some((_best, best_count)) if best_count >= *count => best,
+                _ => Some((sym, *count)),
+            },
+        )
+        .filter(|(_sym, count)| *count > 1);
+
+    let mut summands = Vec::new();
+    match factor {
+        Some((sym, _count)) => {
+            let mut quotient = Polynomial::default();
+            let mut rest = Polynomial::default();
+            for (powers, scale) in poly.0.iter() {
+                match divide_once(powers, sym) {
+                    Some(powers) => quotient = quotient + monomial(powers, scale),
+                    None => rest = rest + monomial(powers.clone(), scale),
+                }
+            }
+            let (negative, term) = signed_polynomial(&quotient);
+            summands.push((negative, product(vec![symbol_term(sym), term])));
+            if !rest.is_zero() {
+                push_summands(&mut summands, signed_polynomial(&rest));
+            }
+        }
+        None => {
+            for (powers, scale) in poly.0.iter() {
+                let term = monomial(powers.clone(), scale);
+                push_summands(&mut summands, signed_polynomial(&term));
+            }
+        }
+    }
+
+    if summands.len() == 1 {
+        return summands.pop().expect("One summand");
+    }
+    let negative = summands[0].0;
+    let summands = summands
+        .into_iter()
+        .map(|(summand_negative, term)| (summand_negative != negative, term))
+        .collect();
+    (negative, Term::Sum(summands))
+}
+
+fn push_summands(summands: &mut Vec<(bool, Term)>, (negative, term): (bool, Term)) {
+    match term {
+        Term::Sum(inner) => summands.extend(
+            inner
+                .into_iter()
+                .map(|(inner_negative, term)| (inner_negative != negative, term)),
+        ),
+        term => summands.push((negative, term)),
+    }
+}
+
+/// Flattens nested products, keeping a constant factor first.
+fn product(factors: Vec<Term>) -> Term {
+    let mut constant = lift_integer(1);
+    let mut flat = Vec::new();
+    for factor in factors {
+        match factor {
+            Term::Constant(value) => constant *= value,
+            Term::Product(inner) => {
+                for factor in inner {
+                    match factor {
+                        Term::Constant(value) => constant *= value,
+                        factor => flat.push(factor),
+                    }
+                }
+            }
+            factor => flat.push(factor),
+        }
+    }
+    if !constant.is_one() || flat.is_empty() {
+        flat.insert(0, Term::Constant(constant));
+    }
+    if flat.len() == 1 {
+        flat.pop().expect("One factor")
+    } else {
+        Term::Product(flat)
+    }
+}
+
+fn symbol_term(sym: &Symbol) -> Term {
+    match sym {
+        Symbol::Named(name) => Term::Variable(name.clone()),
+        Symbol::Apply(function, args) => {
+            Term::Call(*function, args.iter().map(Term::from_symbols).collect())
+        }
+    }
+}
+
+fn monomial(powers: SymbolPowers, scale: &BigRational) -> Polynomial {
+    Polynomial(std::iter::once((powers, scale.clone())).collect())
+}
+
+fn divide_once(powers: &SymbolPowers, sym: &Symbol) -> Option<SymbolPowers> {
+    let mut powers = powers.clone();
+    match powers.remove(sym)? {
+        1 => {}
+        pow => {
+            powers.insert(sym.clone(), pow - 1);
+        }
+    }
+    Some(powers)
+}
+
+/// The positive gcd of the coefficients' numerators over the lcm of their denominators.
+fn rational_content(poly: &Polynomial) -> BigRational {
+    let (numer, denom) = poly.0.values().fold(
+        (num::BigInt::zero(), num::BigInt::one()),
+        |(numer, denom), scale| (numer.gcd(scale.numer()), denom.lcm(scale.denom())),
+    );
+    BigRational::new(numer, denom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Term {
+        Term::Variable(name.to_string())
+    }
+
+    fn poly(terms: &[(isize, &[&str])]) -> Polynomial {
+        terms
+            .iter()
+            .fold(Symbols::zero(), |prev, (scale, names)| {
+                let term = names
+                    .iter()
+                    .fold(Symbols::constant(lift_integer(*scale)), |prod, name| {
+                        &prod * &Symbols::symbol(name)
+                    });
+                prev + term
+            })
+            .numer
+    }
+
+    #[test]
+    fn test_horner_factors_common_symbols() {
+        // a a x + a b - 2 c = a (a x + b) - 2 c
+        assert_eq!(
+            Term::from_polynomial(&poly(&[
+                (1, &["a", "a", "x"]),
+                (1, &["a", "b"]),
+                (-2, &["c"])
+            ])),
+            Term::Sum(vec![
+                (
+                    false,
+                    Term::Product(vec![
+                        var("a"),
+                        Term::Sum(vec![
+                            (false, Term::Product(vec![var("a"), var("x")])),
+                            (false, var("b"))
+                        ])
+                    ])
+                ),
+                (
+                    true,
+                    Term::Product(vec![Term::Constant(lift_integer(2)), var("c")])
+                ),
+            ])
+        );
+
+        // -2 a - 2 b = -(2 (a + b))
+        assert_eq!(
+            Term::from_polynomial(&poly(&[(-2, &["a"]), (-2, &["b"])])),
+            Term::Negate(Box::new(Term::Product(vec![
+                Term::Constant(lift_integer(2)),
+                Term::Sum(vec![(false, var("a")), (false, var("b"))])
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_shared_terms_skip_inner_repeats() {
+        // a b + c appears twice, a b only inside it
+        let inner = Term::from_polynomial(&poly(&[(1, &["a", "b"]), (1, &["c"])]));
+        let outer = Term::Product(vec![var("x"), inner.clone()]);
+        let shared = SharedTerms::new(&[outer.clone(), inner.clone(), outer.clone()]);
+
+        assert_eq!(shared.terms(), &[inner.clone(), outer.clone()]);
+        assert_eq!(shared.index(&outer), Some(1));
+        assert_eq!(shared.index(&Term::Product(vec![var("a"), var("b")])), None);
+    }
+}
@@ -4,30 +4,17 @@ use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenTree};
 use crate::tokens::tokenstream_push;
 use crate::{CodeBasis, Element, MVType};
 
-pub fn element_type_name(basis: &CodeBasis, Element(vs): &Element) -> String {
-    if vs.is_empty() {
+/// The term name in upper case, like `E1E2`, `XY` or `E31`.
+pub fn element_type_name(basis: &CodeBasis, elem: &Element) -> String {
+    if elem.0.is_empty() {
         basis.scalar.to_string()
     } else {
-        let mut name = String::new();
-        for v in vs {
-            name += "E";
-            name += &v.0.to_string();
-        }
-        name
+        element_term_name(basis, elem).to_uppercase()
     }
 }
 
-pub fn element_term_name(Element(vs): &Element) -> String {
-    if vs.is_empty() {
-        "1".to_string()
-    } else {
-        let mut name = String::new();
-        for v in vs {
-            name += "e";
-            name += &v.0.to_string();
-        }
-        name
-    }
+pub fn element_term_name(basis: &CodeBasis, elem: &Element) -> String {
+    basis.basis.element_name(elem)
 }
 
 pub fn type_signiture(basis: &CodeBasis, MVType(es): &MVType) -> TokenStream {
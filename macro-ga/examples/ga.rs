@@ -1,5 +1,3 @@
-use macro_ga;
-
 macro_ga::define_basis!(PGA3, f32, 3, 0, 1);
 
 macro_ga::basis_types!(PGA3);
@@ -11,4 +9,156 @@ fn main() {
 
     let x = f((E1(1.0), E2(2.0)), 3.0);
     println!("Result {:?}", x);
+
+    let wedge = macro_ga::ga!(PGA3, |a: e1 + e2, b: e1 + e2| a ^ b);
+    let y = wedge((E1(1.0), E2(2.0)), (E1(3.0), E2(4.0)));
+    println!("Wedge {:?}", y);
+
+    // Planes x = 1 and y = 2 meet in a line, two points join in a line
+    let intersect = macro_ga::ga!(PGA3, |a: e0 + e1, b: e0 + e2| meet(a, b));
+    let join = macro_ga::ga!(
+        PGA3,
+        |p: e0e1e2 + e0e1e3 + e0e2e3 + e1e2e3, q: e0e1e2 + e0e1e3 + e0e2e3 + e1e2e3| join(p, q)
+    );
+    let l = intersect((E0(-1.0), E1(1.0)), (E0(-2.0), E2(1.0)));
+    let m = join(
+        (E0E1E2(0.0), E0E1E3(0.0), E0E2E3(0.0), E1E2E3(1.0)),
+        (E0E1E2(1.0), E0E1E3(0.0), E0E2E3(0.0), E1E2E3(1.0)),
+    );
+    println!("Intersection {:?}, join {:?}", l, m);
+
+    let inverse = macro_ga::ga!(PGA3, |v: e1 + e2| 1 / v);
+    println!("Inverse {:?}", inverse((E1(3.0), E2(4.0))));
+
+    // A screw motion, exp and log of a general PGA3 bivector round trip
+    let exp = macro_ga::ga!(PGA3, |b: e0e1 + e0e2 + e0e3 + e1e2 + e1e3 + e2e3| exp(b));
+    let log = macro_ga::ga!(
+        PGA3,
+        |m: 1 + e0e1 + e0e1e2e3 + e0e2 + e0e3 + e1e2 + e1e3 + e2e3| log(m)
+    );
+    let (s, E0E1(a), E0E1E2E3(g), E0E2(b), E0E3(c), E1E2(d), E1E3(e), E2E3(f)) = exp((
+        E0E1(0.1),
+        E0E2(0.2),
+        E0E3(0.3),
+        E1E2(0.4),
+        E1E3(0.5),
+        E2E3(0.6),
+    ));
+    println!(
+        "Screw log {:?}",
+        log((
+            s,
+            E0E1(a),
+            E0E1E2E3(g),
+            E0E2(b),
+            E0E3(c),
+            E1E2(d),
+            E1E3(e),
+            E2E3(f)
+        ))
+    );
+
+    // Zero angles take the limit instead of dividing by zero
+    let translator = exp((
+        E0E1(1.0),
+        E0E2(0.0),
+        E0E3(0.0),
+        E1E2(0.0),
+        E1E3(0.0),
+        E2E3(0.0),
+    ));
+    println!("Translator {:?}", translator);
+    println!(
+        "Identity log {:?}",
+        log((
+            1.0,
+            E0E1(0.0),
+            E0E1E2E3(0.0),
+            E0E2(0.0),
+            E0E3(0.0),
+            E1E2(0.0),
+            E1E3(0.0),
+            E2E3(0.0)
+        ))
+    );
+
+    // A normalized rotor, r_1^2 + r_e1e2^2 = 1 shortens the rotated components
+    let rotate = macro_ga::ga!(PGA3, |r: 1 + e1e2 unit, x: e1 + e2| sandwich(r, x));
+    let (c, s) = (0.6f32, 0.8f32);
+    println!("Rotated {:?}", rotate((c, E1E2(s)), (E1(1.0), E2(0.0))));
+
+    // Derivatives of a rotated vector with respect to the rotor components
+    let jacobian = macro_ga::ga_jacobian!(
+        PGA3,
+        r,
+        |r: 1 + e1e2, x: e1 + e2| sandwich(r, x)
+    );
+    println!(
+        "Rotation jacobian {:?}",
+        jacobian((1.0, E1E2(0.0)), (E1(1.0), E2(0.0)))
+    );
+
+    conformal::main();
+    named::main();
+    oriented::main();
+    quaternion::main();
+}
+
+mod conformal {
+    macro_ga::define_basis!(CGA2, f32, conformal 2 null);
+
+    macro_ga::basis_types!(CGA2);
+
+    pub fn main() {
+        // Points embed as null vectors whose inner product is minus half the squared distance
+        let distance = macro_ga::ga!(CGA2, |a: e0 + e1, b: e0 + e1| -2 * grade(up(a) * up(b), 0));
+        println!(
+            "Squared distance {:?}",
+            distance((E0(1.0), E1(0.0)), (E0(4.0), E1(4.0)))
+        );
+
+        let round_trip = macro_ga::ga!(CGA2, |a: e0 + e1| down(2 * up(a)));
+        println!("Round trip {:?}", round_trip((E0(1.0), E1(2.0))));
+    }
+}
+
+mod named {
+    // PGA2 with the degenerate vector named `w`
+    macro_ga::define_basis!(PGA2, f32, [w: 0, x: 1, y: 1]);
+
+    macro_ga::basis_types!(PGA2);
+
+    pub fn main() {
+        let meet = macro_ga::ga!(PGA2, |a: w + x, b: w + y| a ^ b);
+        println!("Meet {:?}", meet((W(1.0), X(1.0)), (W(2.0), Y(1.0))));
+    }
+}
+
+mod oriented {
+    // Blades named as in the usual PGA3 tables
+    macro_ga::define_basis!(PGA3, f32, [0: 0, 1: 1, 2: 1, 3: 1], [e31, e021, e032]);
+
+    macro_ga::basis_types!(PGA3);
+
+    pub fn main() {
+        let wedge = macro_ga::ga!(PGA3, |a: e1 + e3, b: e1 + e3| a ^ b);
+        println!(
+            "Oriented wedge {:?}",
+            wedge((E1(1.0), E3(0.0)), (E1(0.0), E3(1.0)))
+        );
+    }
+}
+
+mod quaternion {
+    // A preset with its named types, i, j and k are bivectors of G3
+    macro_ga::define_basis!(H, f64, QUATERNION);
+
+    macro_ga::basis_types!(H);
+
+    pub fn main() {
+        let mul = macro_ga::ga!(H, |a: quaternion, b: quaternion| a * b);
+        let i: Quaternion = (0.0, I(1.0), J(0.0), K(0.0));
+        let j: Quaternion = (0.0, I(0.0), J(1.0), K(0.0));
+        println!("i j = {:?}", mul(i, j));
+    }
 }
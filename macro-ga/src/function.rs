@@ -1,6 +1,8 @@
 use proc_macro2::TokenTree;
 
 use macro_ga_logic::parse::lambda::parse_lambda;
+use macro_ga_logic::parse::Tokens;
+use macro_ga_logic::CodeBasis;
 
 use crate::use_global_basis;
 
@@ -9,31 +11,63 @@ pub fn function(token_stream: proc_macro::TokenStream) -> proc_macro::TokenStrea
 
     let mut tokens = token_stream.into_iter().peekable();
 
+    let basis = parse_basis(&mut tokens, "ga!");
+
+    let pf = parse_lambda(&basis, &mut tokens)
+        .expect("There was a problem parsing the function inside ga!()");
+
+    let tokens = pf
+        .as_code(&basis)
+        .expect("There was a problem generating code for function inside ga!()");
+
+    proc_macro::TokenStream::from(tokens)
+}
+
+pub fn jacobian(token_stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let token_stream = proc_macro2::TokenStream::from(token_stream);
+
+    let mut tokens = token_stream.into_iter().peekable();
+
+    let basis = parse_basis(&mut tokens, "ga_jacobian!");
+
+    let wrt = match tokens.next() {
+        Some(TokenTree::Ident(wrt)) => wrt.to_string(),
+        _ => panic!("Argument to differentiate by not specified in ga_jacobian!(...)"),
+    };
+    expect_comma(&mut tokens, "argument name");
+
+    let pf = parse_lambda(&basis, &mut tokens)
+        .expect("There was a problem parsing the function inside ga_jacobian!()");
+
+    let tokens = pf
+        .jacobian_as_code(&basis, &wrt)
+        .expect("There was a problem generating code for function inside ga_jacobian!()");
+
+    proc_macro::TokenStream::from(tokens)
+}
+
+fn parse_basis(tokens: &mut Tokens, macro_name: &str) -> CodeBasis {
     let basis = match tokens.next() {
         Some(TokenTree::Ident(basis_name)) => use_global_basis(|bs| {
             bs.get(&basis_name.to_string())
                 .expect("Basis name was not registered, use define_basis!(...)")
                 .clone()
         }),
-        _ => panic!("Basis name not specified in ga!(...)"),
+        _ => panic!("Basis name not specified in {}(...)", macro_name),
     };
+    expect_comma(tokens, "basis name");
+    basis
+}
 
+fn expect_comma(tokens: &mut Tokens, after: &str) {
     match tokens.next() {
         Some(TokenTree::Punct(p)) if p.as_char() == ',' => {}
         token => panic!(
-            "Expected ',' after basis name, got '{}'",
+            "Expected ',' after {}, got '{}'",
+            after,
             token
                 .map(|t| t.to_string())
                 .unwrap_or_else(|| "EOS".to_string())
         ),
     };
-
-    let pf =
-        parse_lambda(&mut tokens).expect("There was a problem parsing the function inside ga!()");
-
-    let tokens = pf
-        .as_code(&basis)
-        .expect("There was a problem generating code for function inside ga!()");
-
-    proc_macro::TokenStream::from(tokens)
 }
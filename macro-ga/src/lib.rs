@@ -6,19 +6,20 @@ use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use proc_macro::TokenStream;
-use proc_macro::TokenTree;
+use proc_macro::{Delimiter, TokenTree};
 
 use macro_ga_logic::CodeBasis;
 
 static mut GLOBAL_BASIS: Option<BTreeMap<String, CodeBasis>> = None;
 
 pub(crate) fn use_global_basis<R, F: FnOnce(&mut BTreeMap<String, CodeBasis>) -> R>(f: F) -> R {
-    let mut basis = unsafe { GLOBAL_BASIS.take().unwrap_or_default() };
+    let global = std::ptr::addr_of_mut!(GLOBAL_BASIS);
+    let mut basis = unsafe { global.replace(None).unwrap_or_default() };
 
     let ret = f(&mut basis);
 
     unsafe {
-        GLOBAL_BASIS = Some(basis);
+        global.write(Some(basis));
     }
 
     ret
@@ -28,7 +29,26 @@ pub(crate) fn use_global_basis<R, F: FnOnce(&mut BTreeMap<String, CodeBasis>) ->
 pub fn define_basis(token_stream: TokenStream) -> TokenStream {
     let tokens: Vec<_> = token_stream.into_iter().collect();
 
-    match tokens.as_slice() {
+    // Any form can end with blade orientations like `[e31, e021]`, replacing only the
+    // orientations of those elements
+    let (tokens, blades) = match tokens.as_slice() {
+        [rest @ .., TokenTree::Punct(comma), TokenTree::Group(blades)]
+            if rest.len() > 3
+                && comma.as_char() == ','
+                && blades.delimiter() == Delimiter::Bracket =>
+        {
+            (rest, Some(blades.stream()))
+        }
+        tokens => (tokens, None),
+    };
+
+    let code_basis = |scalar_type: &proc_macro::Ident, basis| CodeBasis {
+        scalar: scalar_type.to_string(),
+        basis,
+        types: BTreeMap::new(),
+    };
+
+    let (name, mut basis) = match tokens {
         [TokenTree::Ident(name), TokenTree::Punct(comma1), TokenTree::Ident(scalar_type), TokenTree::Punct(comma2), TokenTree::Literal(pos), TokenTree::Punct(comma3), TokenTree::Literal(neg), TokenTree::Punct(comma4), TokenTree::Literal(zero)]
             if [comma1, comma2, comma3, comma4]
                 .iter()
@@ -41,20 +61,46 @@ pub fn define_basis(token_stream: TokenStream) -> TokenStream {
             let zero =
                 usize::from_str(&zero.to_string()).expect("Could not parse zero basis count");
 
-            // TODO check for redefinition with different value!
-            use_global_basis(|basis| {
-                basis.insert(
-                    name.to_string(),
-                    CodeBasis {
-                        scalar: scalar_type.to_string(),
-                        basis: symbolic_ga::basis::Basis {
-                            positive,
-                            negative,
-                            zero,
-                        },
-                    },
-                );
-            });
+            let basis = symbolic_ga::basis::Basis::new(zero, positive, negative);
+            (name, code_basis(scalar_type, basis))
+        }
+        // `conformal 3` has e+ and e- after the Euclidean vectors, `conformal 3 null` has e_o
+        // and e_inf
+        [TokenTree::Ident(name), TokenTree::Punct(comma1), TokenTree::Ident(scalar_type), TokenTree::Punct(comma2), TokenTree::Ident(preset), TokenTree::Literal(dim), frame @ ..]
+            if [comma1, comma2].iter().all(|c| c.as_char() == ',')
+                && preset.to_string() == "conformal" =>
+        {
+            let dimension =
+                usize::from_str(&dim.to_string()).expect("Could not parse conformal dimension");
+            let conformal = match frame {
+                [] => symbolic_ga::conformal::Conformal::new(dimension),
+                [TokenTree::Ident(null)] if null.to_string() == "null" => {
+                    symbolic_ga::conformal::Conformal::null(dimension)
+                }
+                _ => panic!("Expected something like 'define_basis!(CGA3, f32, conformal 3 null)"),
+            };
+            (name, code_basis(scalar_type, conformal.basis()))
+        }
+        // Vectors named in order with their squares, `[w: 0, x: 1, y: 1, z: 1]`
+        [TokenTree::Ident(name), TokenTree::Punct(comma1), TokenTree::Ident(scalar_type), TokenTree::Punct(comma2), TokenTree::Group(vectors)]
+            if [comma1, comma2].iter().all(|c| c.as_char() == ',')
+                && vectors.delimiter() == Delimiter::Bracket =>
+        {
+            let basis = parse_named_vectors(vectors.stream())
+                .and_then(|(names, signature)| {
+                    symbolic_ga::basis::Basis::from_signature(&signature)?.with_names(names)
+                })
+                .unwrap_or_else(|err| panic!("Invalid named basis in define_basis!: {}", err));
+            (name, code_basis(scalar_type, basis))
+        }
+        // A standard algebra with its named types like `motor`, see `presets`
+        [TokenTree::Ident(name), TokenTree::Punct(comma1), TokenTree::Ident(scalar_type), TokenTree::Punct(comma2), TokenTree::Ident(preset)]
+            if [comma1, comma2].iter().all(|c| c.as_char() == ',') =>
+        {
+            let basis =
+                macro_ga_logic::presets::preset(&preset.to_string(), &scalar_type.to_string())
+                    .expect("Invalid preset");
+            (name, basis)
         }
         _tokens => {
             println!("{:?}", tokens);
@@ -62,9 +108,70 @@ pub fn define_basis(token_stream: TokenStream) -> TokenStream {
         }
     };
 
+    if let Some(blades) = blades {
+        let blades = parse_blades(&basis.basis, blades);
+        basis.basis = basis
+            .basis
+            .with_orientations(blades)
+            .expect("Invalid blade orientations");
+    }
+
+    // TODO check for redefinition with different value!
+    use_global_basis(|bases| {
+        bases.insert(name.to_string(), basis);
+    });
+
     TokenStream::new()
 }
 
+/// Each vector is written like `x: 1`, errors name the vector they are about.
+fn parse_named_vectors(token_stream: TokenStream) -> Result<(Vec<String>, Vec<isize>), String> {
+    let tokens: Vec<_> = token_stream.into_iter().collect();
+    let mut names = Vec::new();
+    let mut signature = Vec::new();
+    for vector in tokens.split(|t| matches!(t, TokenTree::Punct(p) if p.as_char() == ',')) {
+        let text = || {
+            let text: Vec<_> = vector.iter().map(|t| t.to_string()).collect();
+            text.join(" ")
+        };
+        let (name, square) = match vector {
+            [name @ TokenTree::Ident(_), TokenTree::Punct(colon), square @ ..]
+            | [name @ TokenTree::Literal(_), TokenTree::Punct(colon), square @ ..]
+                if colon.as_char() == ':' =>
+            {
+                (name, square)
+            }
+            [] => continue,
+            _ => return Err(format!("Expected a vector like 'x: 1', got '{}'", text())),
+        };
+        let square: String = square.iter().map(|t| t.to_string()).collect();
+        let square = match isize::from_str(&square) {
+            Ok(square @ -1..=1) => square,
+            _ => return Err(format!("Vector '{}' must square to 0 or ±1", text())),
+        };
+        names.push(name.to_string());
+        signature.push(square);
+    }
+    Ok((names, signature))
+}
+
+fn parse_blades(
+    basis: &symbolic_ga::basis::Basis,
+    token_stream: TokenStream,
+) -> Vec<Vec<symbolic_ga::basis::Vector>> {
+    token_stream
+        .into_iter()
+        .filter_map(|token| match token {
+            TokenTree::Ident(blade) => Some(
+                symbolic_ga::parse::try_parse_element(basis, &blade.to_string())
+                    .unwrap_or_else(|| panic!("Could not parse blade '{}'", blade)),
+            ),
+            TokenTree::Punct(p) if p.as_char() == ',' => None,
+            token => panic!("Expected a blade name, got '{}'", token),
+        })
+        .collect()
+}
+
 #[proc_macro]
 pub fn basis_types(token_stream: TokenStream) -> TokenStream {
     let mut tokens = token_stream.into_iter();
@@ -85,3 +192,10 @@ pub fn basis_types(token_stream: TokenStream) -> TokenStream {
 pub fn ga(token_stream: TokenStream) -> TokenStream {
     function::function(token_stream)
 }
+
+/// Like `ga!` but the closure returns the derivatives of the body with respect to each component
+/// of one argument, e.g. `ga_jacobian!(PGA3, m, |m: ..., x: ...| sandwich(m, x))`.
+#[proc_macro]
+pub fn ga_jacobian(token_stream: TokenStream) -> TokenStream {
+    function::jacobian(token_stream)
+}
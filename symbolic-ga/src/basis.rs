@@ -1,14 +1,35 @@
 use std::collections::BTreeSet;
 
+use num::{One, Signed, Zero};
+
 use crate::element::Element;
+use crate::parse::try_parse_element;
+use crate::symbols::{lift_integer, Symbols};
 
+/// Vectors ordered zero, then positive, then negative squares. A `metric` replaces the
+/// signature with a full bilinear form, then the counts are unused. Vectors are named `e0`,
+/// `e1`, ... unless `names` gives one name per vector. Elements keep their vectors sorted, but
+/// `orientations` can name a blade with its vectors in another order, like `e31` for `-e13`.
+/// `blade_names` gives some blades a name of their own, like `k` for `e21`. `conformal` marks
+/// a basis made by `Conformal::basis`, which enables `up` and `down`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Basis {
     pub zero: usize,
     pub positive: usize,
     pub negative: usize,
+    pub metric: Option<Metric>,
+    pub names: Option<Vec<String>>,
+    pub orientations: Vec<Vec<Vector>>,
+    pub blade_names: Vec<(Vec<Vector>, String)>,
+    pub conformal: bool,
 }
 
+/// The symmetric matrix of inner products `e_i . e_j`, with rational or symbolic entries.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Metric(Vec<Vec<Symbols>>);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SquaredElement {
     Zero,
@@ -19,10 +40,21 @@ pub enum SquaredElement {
 pub type Grade = usize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector(pub usize);
 
 impl Vector {
+    /// Fails for a metric where the vector does not square to 0 or ±1.
     pub fn square(&self, basis: &Basis) -> Result<SquaredElement, String> {
+        if let Some(metric) = &basis.metric {
+            let square = metric.get(*self, *self)?;
+            return match square.constant_value() {
+                Some(c) if c.is_zero() => Ok(SquaredElement::Zero),
+                Some(c) if c.is_one() => Ok(SquaredElement::One),
+                Some(c) if c.abs().is_one() => Ok(SquaredElement::MinusOne),
+                _ => Err(format!("e{} squares to {}, not 0 or ±1", self.0, square)),
+            };
+        }
         match self.0 {
             idx if idx < basis.zero => Ok(SquaredElement::Zero),
             idx if idx < basis.zero + basis.positive => Ok(SquaredElement::One),
@@ -34,11 +66,260 @@ impl Vector {
     }
 }
 
+impl Metric {
+    pub fn new(rows: Vec<Vec<Symbols>>) -> Result<Metric, String> {
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != rows.len() {
+                return Err(format!(
+                    "Metric row {} does not have {} entries",
+                    i,
+                    rows.len()
+                ));
+            }
+            if (0..i).any(|j| row[j] != rows[j][i]) {
+                return Err(format!("Metric row {} is not symmetric", i));
+            }
+        }
+        Ok(Metric(rows))
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn get(&self, lhs: Vector, rhs: Vector) -> Result<&Symbols, String> {
+        self.0
+            .get(lhs.0)
+            .and_then(|row| row.get(rhs.0))
+            .ok_or_else(|| format!("Vector index is larger than metric: {}", lhs.0.max(rhs.0)))
+    }
+
+    /// A diagonal matrix with entries 0 and ±1.
+    pub fn is_orthonormal(&self) -> bool {
+        self.0.iter().enumerate().all(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(j, entry)| match entry.constant_value() {
+                    Some(c) if i == j => c.is_zero() || c.abs().is_one(),
+                    Some(c) => c.is_zero(),
+                    None => false,
+                })
+        })
+    }
+}
+
 impl Basis {
+    /// Vectors with indices named `e0`, `e1`, ... and no metric.
+    pub const fn new(zero: usize, positive: usize, negative: usize) -> Basis {
+        Basis {
+            zero,
+            positive,
+            negative,
+            metric: None,
+            names: None,
+            orientations: Vec::new(),
+            blade_names: Vec::new(),
+            conformal: false,
+        }
+    }
+
+    pub fn from_metric(metric: Metric) -> Basis {
+        Basis {
+            metric: Some(metric),
+            ..Basis::new(0, 0, 0)
+        }
+    }
+
+    /// Each vector squares to the given 0 or ±1, in any order.
+    pub fn from_signature(signature: &[isize]) -> Result<Basis, String> {
+        let rows = signature
+            .iter()
+            .enumerate()
+            .map(|(i, square)| match square {
+                -1..=1 => Ok((0..signature.len())
+                    .map(|j| Symbols::constant(lift_integer(if i == j { *square } else { 0 })))
+                    .collect()),
+                _ => Err(format!("Vectors must square to 0 or ±1, not {}", square)),
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Basis::from_metric(Metric::new(rows)?))
+    }
+
+    /// Names are letters and digits and unique. Digits give compact blade names, vectors `0` to
+    /// `3` make blades like `e0`, `e31` and `e0123`. In `ga!` a vector name takes the place of
+    /// a symbol with the same name and cannot name an argument.
+    pub fn with_names(self, names: Vec<String>) -> Result<Basis, String> {
+        if names.len() != self.dimension() {
+            return Err(format!(
+                "Expected {} vector names, given {}",
+                self.dimension(),
+                names.len()
+            ));
+        }
+        for (idx, name) in names.iter().enumerate() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("Invalid vector name: '{}'", name));
+            }
+            if names[..idx].contains(name) {
+                return Err(format!("Repeated vector name: '{}'", name));
+            }
+        }
+        Ok(Basis {
+            names: Some(names),
+            ..self
+        })
+    }
+
+    /// Each blade is a reordering of distinct vectors, at most one for each element. Blades
+    /// replace the earlier orientation of their element and keep the others, but a named blade
+    /// keeps its orientation.
+    pub fn with_orientations(self, blades: Vec<Vec<Vector>>) -> Result<Basis, String> {
+        self.check_blades(&blades)?;
+        for blade in blades.iter() {
+            let named = self
+                .blade_names
+                .iter()
+                .find(|(named, _name)| same_element(named, blade));
+            if let Some((named, name)) = named.filter(|(named, _name)| named != blade) {
+                return Err(format!(
+                    "Blade {:?} is named '{}' with orientation {:?}",
+                    blade, name, named
+                ));
+            }
+        }
+        Ok(Basis {
+            orientations: merge_orientations(&self.orientations, blades),
+            ..self
+        })
+    }
+
+    /// Names start with a letter and are not already an element or blade name, each blade is
+    /// also oriented as given. Earlier names are kept, but a blade can only have one name.
+    pub fn with_blade_names(self, blades: Vec<(Vec<Vector>, String)>) -> Result<Basis, String> {
+        for (idx, (blade, name)) in blades.iter().enumerate() {
+            if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+                || !name.chars().all(|c| c.is_ascii_alphanumeric())
+                || try_parse_element(&self, name).is_some()
+            {
+                return Err(format!("Invalid blade name: '{}'", name));
+            }
+            if blades[..idx].iter().any(|(_blade, other)| other == name) {
+                return Err(format!("Repeated blade name: '{}'", name));
+            }
+            let named = self
+                .blade_names
+                .iter()
+                .find(|(named, _name)| same_element(named, blade));
+            if let Some((_named, other)) = named {
+                return Err(format!("Blade {:?} is already named '{}'", blade, other));
+            }
+        }
+        let orientations: Vec<_> = blades.iter().map(|(blade, _name)| blade.clone()).collect();
+        self.check_blades(&orientations)?;
+        let blade_names = self.blade_names.iter().cloned().chain(blades).collect();
+        Ok(Basis {
+            orientations: merge_orientations(&self.orientations, orientations),
+            blade_names,
+            ..self
+        })
+    }
+
+    /// Blades of distinct vectors in the basis, at most one for each element.
+    fn check_blades(&self, blades: &[Vec<Vector>]) -> Result<(), String> {
+        let mut elems = BTreeSet::new();
+        for blade in blades.iter() {
+            let elem = Element(blade.iter().cloned().collect());
+            if elem.0.len() != blade.len() || blade.iter().any(|v| v.0 >= self.dimension()) {
+                return Err(format!("Invalid blade orientation: {:?}", blade));
+            }
+            if !elems.insert(elem) {
+                return Err(format!("Repeated blade orientation: {:?}", blade));
+            }
+        }
+        Ok(())
+    }
+
+    /// The vectors of the element in the order of its blade, and whether that is an odd
+    /// permutation so the blade is minus the element.
+    pub fn orientation(&self, elem: &Element) -> (Vec<Vector>, bool) {
+        let blade = self
+            .orientations
+            .iter()
+            .find(|blade| blade.len() == elem.0.len() && blade.iter().all(|v| elem.0.contains(v)));
+        match blade {
+            Some(blade) => {
+                let inversions = (0..blade.len())
+                    .flat_map(|i| (i + 1..blade.len()).map(move |j| (i, j)))
+                    .filter(|(i, j)| blade[*i] > blade[*j])
+                    .count();
+                (blade.clone(), inversions % 2 == 1)
+            }
+            None => (elem.0.iter().cloned().collect(), false),
+        }
+    }
+
+    /// The blade's own name, else the oriented blade's vector names run together, with an `e`
+    /// in front when that would not start with a letter. The scalar is `1`.
+    pub fn element_name(&self, elem: &Element) -> String {
+        if elem.0.is_empty() {
+            return "1".to_string();
+        }
+        if let Some((_blade, name)) = self.blade_names.iter().find(|(blade, _name)| {
+            blade.len() == elem.0.len() && blade.iter().all(|v| elem.0.contains(v))
+        }) {
+            return name.clone();
+        }
+        let name: String = self
+            .orientation(elem)
+            .0
+            .into_iter()
+            .map(|v| self.vector_name(v))
+            .collect();
+        match name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            true => name,
+            false => format!("e{}", name),
+        }
+    }
+
+    pub fn vector_name(&self, v: Vector) -> String {
+        match self.names.as_ref().and_then(|names| names.get(v.0)) {
+            Some(name) => name.clone(),
+            None => format!("e{}", v.0),
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        match &self.metric {
+            Some(metric) => metric.dimension(),
+            None => self.zero + self.positive + self.negative,
+        }
+    }
+
+    /// Without a metric, or with one given by a signature, every element product is a single
+    /// signed element.
+    pub fn is_orthonormal(&self) -> bool {
+        self.metric.as_ref().is_none_or(Metric::is_orthonormal)
+    }
+
+    /// The inner product of two basis vectors.
+    pub fn inner(&self, lhs: Vector, rhs: Vector) -> Result<Symbols, String> {
+        if let Some(metric) = &self.metric {
+            return metric.get(lhs, rhs).cloned();
+        }
+        if lhs != rhs {
+            lhs.square(self)?;
+            rhs.square(self)?;
+            return Ok(Symbols::zero());
+        }
+        Ok(Symbols::constant(lift_integer(match lhs.square(self)? {
+            SquaredElement::Zero => 0,
+            SquaredElement::One => 1,
+            SquaredElement::MinusOne => -1,
+        })))
+    }
+
     pub fn vectors(&self) -> Vec<Vector> {
-        (0..self.zero + self.positive + self.negative)
-            .map(Vector)
-            .collect()
+        (0..self.dimension()).map(Vector).collect()
     }
 
     pub fn grade(&self, n: Grade) -> Vec<Element> {
@@ -49,7 +330,7 @@ impl Basis {
     }
 
     pub fn elements(&self) -> Vec<Element> {
-        (0..=self.zero + self.positive + self.negative)
+        (0..=self.dimension())
             .flat_map(|grade| self.grade(grade))
             .collect()
     }
@@ -70,15 +351,123 @@ fn combinations<T: Clone + Ord>(xs: &[T], n: usize) -> Vec<BTreeSet<T>> {
     }
 }
 
+fn same_element(lhs: &[Vector], rhs: &[Vector]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().all(|v| rhs.contains(v))
+}
+
+/// The `blades` and the orientations of other elements, in element order.
+fn merge_orientations(orientations: &[Vec<Vector>], blades: Vec<Vec<Vector>>) -> Vec<Vec<Vector>> {
+    let mut merged: Vec<_> = orientations
+        .iter()
+        .filter(|blade| blades.iter().all(|other| !same_element(blade, other)))
+        .cloned()
+        .collect();
+    merged.extend(blades);
+    merged.sort_by_key(|blade| Element(blade.iter().cloned().collect()));
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const G3: Basis = Basis {
-        zero: 0,
-        positive: 3,
-        negative: 0,
-    };
+    const G3: Basis = Basis::new(0, 3, 0);
+
+    #[test]
+    fn test_signature_and_names() -> Result<(), String> {
+        let basis = Basis::from_signature(&[1, 0, -1])?.with_names(
+            ["x", "w", "t"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )?;
+        assert!(basis.is_orthonormal());
+        assert_eq!(Vector(1).square(&basis)?, SquaredElement::Zero);
+        assert_eq!(Vector(2).square(&basis)?, SquaredElement::MinusOne);
+        assert_eq!(basis.vector_name(Vector(2)), "t");
+        assert_eq!(G3.vector_name(Vector(2)), "e2");
+
+        assert!(Basis::from_signature(&[1, 2]).is_err());
+        assert!(G3.clone().with_names(vec!["x".to_string()]).is_err());
+        let repeated = ["x", "y", "x"].iter().map(|name| name.to_string());
+        assert!(G3.clone().with_names(repeated.collect()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_orientations() -> Result<(), String> {
+        let digits = ["0", "1", "2", "3"].iter().map(|name| name.to_string());
+        let pga3 = Basis::from_signature(&[0, 1, 1, 1])?
+            .with_names(digits.collect())?
+            .with_orientations(vec![
+                vec![Vector(3), Vector(1)],
+                vec![Vector(0), Vector(3), Vector(2)],
+            ])?;
+        let elem = |vs: &[usize]| Element(vs.iter().cloned().map(Vector).collect());
+
+        assert_eq!(
+            pga3.orientation(&elem(&[1, 3])),
+            (vec![Vector(3), Vector(1)], true)
+        );
+        assert!(pga3.orientation(&elem(&[0, 2, 3])).1);
+        assert!(!pga3.orientation(&elem(&[1, 2])).1);
+        assert_eq!(pga3.element_name(&elem(&[1, 3])), "e31");
+        assert_eq!(pga3.element_name(&elem(&[0, 2, 3])), "e032");
+        assert_eq!(pga3.element_name(&elem(&[0, 1, 2, 3])), "e0123");
+        assert_eq!(G3.element_name(&elem(&[0, 2])), "e0e2");
+        assert_eq!(G3.element_name(&elem(&[])), "1");
+
+        // Later orientations replace those of the same element and keep the others
+        let reoriented = pga3.with_orientations(vec![vec![Vector(1), Vector(3)]])?;
+        assert_eq!(reoriented.element_name(&elem(&[1, 3])), "e13");
+        assert_eq!(reoriented.element_name(&elem(&[0, 2, 3])), "e032");
+
+        let repeated = vec![vec![Vector(1), Vector(0)], vec![Vector(0), Vector(1)]];
+        assert!(G3.clone().with_orientations(repeated).is_err());
+        assert!(G3.clone().with_orientations(vec![vec![Vector(3)]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blade_names() -> Result<(), String> {
+        let elem = |vs: &[usize]| Element(vs.iter().cloned().map(Vector).collect());
+        let named = G3
+            .clone()
+            .with_blade_names(vec![(vec![Vector(2), Vector(1)], "k".to_string())])?;
+        assert_eq!(named.element_name(&elem(&[1, 2])), "k");
+        assert!(named.orientation(&elem(&[1, 2])).1);
+        assert_eq!(named.element_name(&elem(&[0, 1])), "e0e1");
+        // Other orientations keep the named blade's
+        let oriented = named.with_orientations(vec![vec![Vector(1), Vector(0)]])?;
+        assert_eq!(oriented.element_name(&elem(&[1, 2])), "k");
+        assert!(oriented.orientation(&elem(&[0, 1])).1);
+        assert!(oriented
+            .clone()
+            .with_orientations(vec![vec![Vector(1), Vector(2)]])
+            .is_err());
+
+        // Later names are added to the earlier ones, each blade has one name
+        let twice =
+            oriented.with_blade_names(vec![(vec![Vector(0), Vector(2)], "j".to_string())])?;
+        assert_eq!(twice.element_name(&elem(&[1, 2])), "k");
+        assert_eq!(twice.element_name(&elem(&[0, 2])), "j");
+        assert!(twice.orientation(&elem(&[0, 1])).1);
+        let renamed = vec![(vec![Vector(1), Vector(2)], "i".to_string())];
+        assert!(twice.clone().with_blade_names(renamed).is_err());
+        let repeated = vec![(vec![Vector(0), Vector(1)], "k".to_string())];
+        assert!(twice.with_blade_names(repeated).is_err());
+
+        let name = |blade: Vec<usize>, name: &str| {
+            G3.clone().with_blade_names(vec![(
+                blade.into_iter().map(Vector).collect(),
+                name.to_string(),
+            )])
+        };
+        assert!(name(vec![1, 2], "e1").is_err());
+        assert!(name(vec![1, 2], "1k").is_err());
+        assert!(name(vec![1, 1], "k").is_err());
+        Ok(())
+    }
 
     #[test]
     fn test_grade_zero() {
@@ -0,0 +1,261 @@
+use std::collections::BTreeSet;
+
+use num::rational::BigRational;
+
+use crate::basis::{Basis, Metric, Vector};
+use crate::element::Element;
+use crate::multivector::MultiVector;
+use crate::symbols::{lift_integer, Symbols};
+
+/// Conformal geometric algebra of an n dimensional Euclidean space. Vectors `e0` to `e(n-1)`
+/// are Euclidean, followed either by the orthogonal pair `e+`, `e-` squaring to 1 and -1, or by
+/// the null pair `e_o`, `e_inf` with `e_o . e_inf = -1`. The two are related by
+/// `e_o = (e- - e+) / 2` and `e_inf = e+ + e-`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conformal {
+    pub dimension: usize,
+    pub null: bool,
+}
+
+impl Conformal {
+    pub fn new(dimension: usize) -> Conformal {
+        Conformal {
+            dimension,
+            null: false,
+        }
+    }
+
+    pub fn null(dimension: usize) -> Conformal {
+        Conformal {
+            dimension,
+            null: true,
+        }
+    }
+
+    /// The algebra of a basis made by `basis`, recognising the frame by its signature or
+    /// metric.
+    pub fn from_basis(basis: &Basis) -> Option<Conformal> {
+        if !basis.conformal {
+            return None;
+        }
+        match &basis.metric {
+            None if basis.zero == 0 && basis.negative == 1 && basis.positive >= 1 => {
+                Some(Conformal::new(basis.positive - 1))
+            }
+            Some(metric) => {
+                let conformal = Conformal::null(metric.dimension().checked_sub(2)?);
+                match conformal.basis().metric.as_ref() == Some(metric) {
+                    true => Some(conformal),
+                    false => None,
+                }
+            }
+            None => None,
+        }
+    }
+
+    pub fn basis(&self) -> Basis {
+        if !self.null {
+            return Basis {
+                conformal: true,
+                ..Basis::new(0, self.dimension + 1, 1)
+            };
+        }
+        let n = self.dimension + 2;
+        let rows = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| {
+                        Symbols::constant(lift_integer(match (i, j) {
+                            _ if i == j && i < self.dimension => 1,
+                            _ if i >= self.dimension && j >= self.dimension && i != j => -1,
+                            _ => 0,
+                        }))
+                    })
+                    .collect()
+            })
+            .collect();
+        Basis {
+            conformal: true,
+            ..Basis::from_metric(Metric::new(rows).expect("Conformal metric is symmetric"))
+        }
+    }
+
+    pub fn origin(&self) -> MultiVector {
+        match self.null {
+            true => vector(self.dimension, 1),
+            false => (vector(self.dimension + 1, 1) + vector(self.dimension, -1)).scale(&half()),
+        }
+    }
+
+    pub fn infinity(&self) -> MultiVector {
+        match self.null {
+            true => vector(self.dimension + 1, 1),
+            false => vector(self.dimension, 1) + vector(self.dimension + 1, 1),
+        }
+    }
+
+    /// Rewrites a multivector in the other frame, mapping each element as the outer product of
+    /// its vectors' images.
+    pub fn convert(&self, mv: &MultiVector, to: &Conformal) -> Result<MultiVector, String> {
+        if self.dimension != to.dimension {
+            return Err(format!(
+                "Cannot convert between conformal dimensions {} and {}",
+                self.dimension, to.dimension
+            ));
+        }
+        if self.null == to.null {
+            return Ok(mv.clone());
+        }
+
+        let basis = to.basis();
+        let mut result = MultiVector::default();
+        for (elem, sym) in mv.0.iter() {
+            let mut image = scalar(sym.clone());
+            for v in elem.0.iter() {
+                image = image.outer(&basis, &self.vector_image(*v, to))?;
+            }
+            result = result + image;
+        }
+        Ok(result)
+    }
+
+    /// Embeds a Euclidean vector as the null vector `e_o + x + x^2 / 2 e_inf`.
+    pub fn up(&self, x: &MultiVector) -> Result<MultiVector, String> {
+        if x.0.keys().any(|elem| !self.is_euclidean(elem)) {
+            return Err("up needs a Euclidean vector".to_string());
+        }
+        let basis = self.basis();
+        let square = x
+            .multiply(&basis, x)?
+            .scalar_part()
+            .unwrap_or_else(Symbols::zero);
+        Ok(self.origin() + x.clone() + self.infinity().scale_symbols(&square.scale(&half())))
+    }
+
+    /// The Euclidean part of a point after normalising so that `-p . e_inf = 1`.
+    pub fn down(&self, p: &MultiVector) -> Result<MultiVector, String> {
+        let weight = p
+            .left_contraction(&self.basis(), &self.infinity())?
+            .scalar_part()
+            .ok_or_else(|| "down needs a conformal vector".to_string())?
+            .invert();
+        let euclidean = MultiVector(
+            p.0.iter()
+                .filter(|(elem, _sym)| self.is_euclidean(elem))
+                .map(|(elem, sym)| (elem.clone(), sym.clone()))
+                .collect(),
+        );
+        Ok(euclidean.scale_symbols(&weight.recip()?))
+    }
+
+    fn is_euclidean(&self, elem: &Element) -> bool {
+        elem.0.len() == 1 && elem.0.iter().all(|v| v.0 < self.dimension)
+    }
+
+    /// A basis vector of this frame written in the other one.
+    fn vector_image(&self, v: Vector, to: &Conformal) -> MultiVector {
+        let n = self.dimension;
+        match v.0 {
+            idx if idx < n => vector(idx, 1),
+            // e_o and e_inf
+            idx if self.null && idx == n => to.origin(),
+            _ if self.null => to.infinity(),
+            // e+ = e_inf / 2 - e_o and e- = e_inf / 2 + e_o
+            idx => {
+                let half_inf = to.infinity().scale(&half());
+                let origin = to.origin();
+                match idx == n {
+                    true => half_inf + origin.scale(&lift_integer(-1)),
+                    false => half_inf + origin,
+                }
+            }
+        }
+    }
+}
+
+fn half() -> BigRational {
+    BigRational::new(1.into(), 2.into())
+}
+
+fn vector(idx: usize, scale: isize) -> MultiVector {
+    let elem = Element(std::iter::once(Vector(idx)).collect());
+    let sym = Symbols::constant(lift_integer(scale));
+    MultiVector(std::iter::once((elem, sym)).collect())
+}
+
+fn scalar(sym: Symbols) -> MultiVector {
+    MultiVector::default() + MultiVector(std::iter::once((Element(BTreeSet::new()), sym)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(conformal: &Conformal, src: &str) -> Result<MultiVector, String> {
+        conformal.up(&MultiVector::parse(&conformal.basis(), src)?)
+    }
+
+    #[test]
+    fn test_up_down_round_trip() -> Result<(), String> {
+        for conformal in [Conformal::new(3), Conformal::null(3)].iter() {
+            let basis = conformal.basis();
+            assert_eq!(Conformal::from_basis(&basis), Some(*conformal));
+            assert_eq!(
+                Conformal::from_basis(&Basis {
+                    conformal: false,
+                    ..basis.clone()
+                }),
+                None
+            );
+
+            let x = point(conformal, "a*e0 + b*e1 + c*e2")?;
+            let y = point(conformal, "d*e0")?;
+            assert_eq!(x.multiply(&basis, &x)?, MultiVector::default());
+            // x . y = -|x - y|^2 / 2
+            assert_eq!(
+                x.left_contraction(&basis, &y)?,
+                MultiVector::parse(&basis, "-1/2*((a - d)^2 + b^2 + c^2)")?
+            );
+            assert_eq!(
+                conformal.down(&x.scale(&lift_integer(3)))?,
+                MultiVector::parse(&basis, "a*e0 + b*e1 + c*e2")?
+            );
+        }
+        // Spacetime has the signature of a conformal plane but is not one
+        assert_eq!(Conformal::from_basis(&Basis::new(0, 3, 1)), None);
+        assert!(Conformal::new(3)
+            .up(&MultiVector::parse(&Conformal::new(3).basis(), "e3").unwrap())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_change_of_basis() -> Result<(), String> {
+        let (orthogonal, null) = (Conformal::new(2), Conformal::null(2));
+        assert_eq!(
+            orthogonal.convert(&orthogonal.origin(), &null)?,
+            null.origin()
+        );
+        assert_eq!(
+            null.convert(&null.infinity(), &orthogonal)?,
+            orthogonal.infinity()
+        );
+
+        // Points and the bivector e_o ^ e_inf = -e+ ^ e- agree in either frame
+        let x = point(&orthogonal, "a*e0 + b*e1")?;
+        assert_eq!(orthogonal.convert(&x, &null)?, point(&null, "a*e0 + b*e1")?);
+        let flat = orthogonal
+            .origin()
+            .outer(&orthogonal.basis(), &orthogonal.infinity())?;
+        assert_eq!(flat, MultiVector::parse(&orthogonal.basis(), "-e23")?);
+        assert_eq!(
+            orthogonal.convert(&flat, &null)?,
+            MultiVector::parse(&null.basis(), "e23")?
+        );
+        assert_eq!(
+            null.convert(&orthogonal.convert(&x, &null)?, &orthogonal)?,
+            x
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use crate::basis::{Basis, SquaredElement, Vector};
+use crate::basis::{Basis, Grade, SquaredElement, Vector};
+use crate::symbols::{lift_integer, Symbols};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element(pub BTreeSet<Vector>);
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,6 +13,8 @@ pub enum SimplifiedElement {
     Zero,
     Positive(Element),
     Negative(Element),
+    /// Products in a basis with a non-orthonormal metric give sums of elements.
+    Sum(BTreeMap<Element, Symbols>),
 }
 
 fn pop_first_vector(vs: &mut BTreeSet<Vector>) -> Option<Vector> {
@@ -19,11 +23,29 @@ fn pop_first_vector(vs: &mut BTreeSet<Vector>) -> Option<Vector> {
 }
 
 impl SimplifiedElement {
-    pub fn elems_and_sign(self) -> (SquaredElement, Element) {
+    /// Single elements with a coefficient of ±1 are kept signed.
+    pub fn from_terms(terms: BTreeMap<Element, Symbols>) -> SimplifiedElement {
+        let mut iter = terms.iter();
+        if let (Some((es, coef)), None) = (iter.next(), iter.next()) {
+            match coef.constant_value() {
+                Some(c) if c == lift_integer(1) => return SimplifiedElement::Positive(es.clone()),
+                Some(c) if c == lift_integer(-1) => return SimplifiedElement::Negative(es.clone()),
+                _ => {}
+            }
+        }
+        if terms.is_empty() {
+            SimplifiedElement::Zero
+        } else {
+            SimplifiedElement::Sum(terms)
+        }
+    }
+
+    pub fn into_terms(self) -> BTreeMap<Element, Symbols> {
         match self {
-            SimplifiedElement::Zero => (SquaredElement::Zero, Element(BTreeSet::new())),
-            SimplifiedElement::Positive(es) => (SquaredElement::One, es),
-            SimplifiedElement::Negative(es) => (SquaredElement::MinusOne, es),
+            SimplifiedElement::Zero => BTreeMap::new(),
+            SimplifiedElement::Positive(es) => unit_term(es),
+            SimplifiedElement::Negative(es) => unit_term(es).into_iter().map(negate).collect(),
+            SimplifiedElement::Sum(terms) => terms,
         }
     }
 
@@ -32,18 +54,82 @@ impl SimplifiedElement {
             SimplifiedElement::Zero => SimplifiedElement::Zero,
             SimplifiedElement::Positive(es) => SimplifiedElement::Negative(es),
             SimplifiedElement::Negative(es) => SimplifiedElement::Positive(es),
+            SimplifiedElement::Sum(terms) => {
+                SimplifiedElement::Sum(terms.into_iter().map(negate).collect())
+            }
         }
     }
 
-    pub fn map(self, f: impl FnOnce(Element) -> Element) -> SimplifiedElement {
+    pub fn map(self, f: impl Fn(Element) -> Element) -> SimplifiedElement {
         match self {
             SimplifiedElement::Zero => SimplifiedElement::Zero,
             SimplifiedElement::Positive(es) => SimplifiedElement::Positive(f(es)),
             SimplifiedElement::Negative(es) => SimplifiedElement::Negative(f(es)),
+            SimplifiedElement::Sum(terms) => {
+                let mut mapped = BTreeMap::new();
+                for (es, coef) in terms {
+                    add_term(&mut mapped, f(es), coef);
+                }
+                SimplifiedElement::from_terms(mapped)
+            }
+        }
+    }
+
+    /// Applies a further signed operation to the element, combining the signs.
+    pub fn and_then(
+        self,
+        f: impl Fn(Element) -> Result<SimplifiedElement, String>,
+    ) -> Result<SimplifiedElement, String> {
+        match self {
+            SimplifiedElement::Zero => Ok(SimplifiedElement::Zero),
+            SimplifiedElement::Positive(es) => f(es),
+            SimplifiedElement::Negative(es) => f(es).map(SimplifiedElement::flip),
+            SimplifiedElement::Sum(terms) => {
+                let mut result = BTreeMap::new();
+                for (es, coef) in terms {
+                    for (es, inner) in f(es)?.into_terms() {
+                        add_term(&mut result, es, &coef * &inner);
+                    }
+                }
+                Ok(SimplifiedElement::from_terms(result))
+            }
+        }
+    }
+
+    /// Keeps the elements whose grade passes `keep`.
+    pub fn project(self, keep: impl Fn(Grade) -> bool) -> SimplifiedElement {
+        match self {
+            SimplifiedElement::Positive(ref es) | SimplifiedElement::Negative(ref es)
+                if keep(es.grade()) =>
+            {
+                self
+            }
+            SimplifiedElement::Sum(terms) => SimplifiedElement::from_terms(
+                terms
+                    .into_iter()
+                    .filter(|(es, _coef)| keep(es.grade()))
+                    .collect(),
+            ),
+            _ => SimplifiedElement::Zero,
         }
     }
 }
 
+fn unit_term(es: Element) -> BTreeMap<Element, Symbols> {
+    std::iter::once((es, Symbols::constant(lift_integer(1)))).collect()
+}
+
+fn negate((es, coef): (Element, Symbols)) -> (Element, Symbols) {
+    (es, coef.invert())
+}
+
+fn add_term(terms: &mut BTreeMap<Element, Symbols>, es: Element, coef: Symbols) {
+    let sum = terms.remove(&es).unwrap_or_else(Symbols::zero) + coef;
+    if !sum.is_zero() {
+        terms.insert(es, sum);
+    }
+}
+
 impl Element {
     fn multiply_vector_left(
         self,
@@ -78,39 +164,280 @@ impl Element {
         }
     }
 
+    /// The geometric product. Elements are outer products of their vectors, which in an
+    /// orthonormal basis multiply to a single signed element.
     pub fn multiply(&self, basis: &Basis, rhs: &Element) -> Result<SimplifiedElement, String> {
-        let mut elems: Vec<Vector> = self.0.iter().cloned().collect();
-        elems.reverse();
+        if !basis.is_orthonormal() {
+            return Ok(SimplifiedElement::from_terms(
+                self.metric_multiply(basis, rhs)?,
+            ));
+        }
 
         let mut curr = SimplifiedElement::Positive(Element(rhs.0.clone()));
+        for lhs_v in self.0.iter().rev() {
+            curr = curr.and_then(|es| es.multiply_vector_left(basis, *lhs_v))?;
+        }
+        Ok(curr)
+    }
 
-        for lhs_rhs in elems {
-            match curr.elems_and_sign() {
-                (SquaredElement::Zero, _) => return Ok(SimplifiedElement::Zero),
-                (sign, elems) => match elems.multiply_vector_left(basis, lhs_rhs)? {
-                    SimplifiedElement::Zero => return Ok(SimplifiedElement::Zero),
-                    SimplifiedElement::Positive(es) => {
-                        curr = if sign == SquaredElement::MinusOne {
-                            SimplifiedElement::Negative(es)
-                        } else {
-                            SimplifiedElement::Positive(es)
-                        }
-                    }
-                    SimplifiedElement::Negative(es) => {
-                        curr = if sign == SquaredElement::MinusOne {
-                            SimplifiedElement::Positive(es)
-                        } else {
-                            SimplifiedElement::Negative(es)
-                        }
-                    }
-                },
+    /// The geometric product for any metric. Splitting off the first vector as `a ^ A`, which
+    /// is `a A - a . A`, gives `(a ^ A) B = a (A B) - (a . A) B`.
+    fn metric_multiply(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+    ) -> Result<BTreeMap<Element, Symbols>, String> {
+        let mut rest = self.0.clone();
+        let first = match pop_first_vector(&mut rest) {
+            Some(first) => first,
+            None => return Ok(unit_term(rhs.clone())),
+        };
+        let rest = Element(rest);
+
+        let mut terms = BTreeMap::new();
+        for (es, coef) in rest.metric_multiply(basis, rhs)? {
+            for (es, inner) in es.vector_product(basis, first)? {
+                add_term(&mut terms, es, &coef * &inner);
             }
         }
+        for (es, coef) in rest.vector_contraction(basis, first)? {
+            for (es, inner) in es.metric_multiply(basis, rhs)? {
+                add_term(&mut terms, es, (&coef * &inner).invert());
+            }
+        }
+        Ok(terms)
+    }
 
-        Ok(curr)
+    /// `v self = v . self + v ^ self` for any metric.
+    fn vector_product(
+        &self,
+        basis: &Basis,
+        v: Vector,
+    ) -> Result<BTreeMap<Element, Symbols>, String> {
+        let mut terms = self.vector_contraction(basis, v)?;
+        if !self.0.contains(&v) {
+            let mut vs = self.0.clone();
+            vs.insert(v);
+            let coef = Symbols::constant(lift_integer(1));
+            let coef = match self.0.iter().filter(|u| **u < v).count() % 2 {
+                0 => coef,
+                _ => coef.invert(),
+            };
+            add_term(&mut terms, Element(vs), coef);
+        }
+        Ok(terms)
+    }
+
+    /// `v . (u1 ^ ... ^ uk)`, the sum of `(v . ui)` times the element without `ui`, alternating
+    /// in sign.
+    fn vector_contraction(
+        &self,
+        basis: &Basis,
+        v: Vector,
+    ) -> Result<BTreeMap<Element, Symbols>, String> {
+        let mut terms = BTreeMap::new();
+        for (idx, u) in self.0.iter().enumerate() {
+            let inner = basis.inner(v, *u)?;
+            if inner.is_zero() {
+                continue;
+            }
+            let mut vs = self.0.clone();
+            vs.remove(u);
+            let coef = if idx % 2 == 0 { inner } else { inner.invert() };
+            add_term(&mut terms, Element(vs), coef);
+        }
+        Ok(terms)
+    }
+
+    pub fn grade(&self) -> Grade {
+        self.0.len()
+    }
+
+    /// The outer (wedge) product, zero whenever the elements share a vector. The sign only
+    /// counts the swaps to sort the vectors so this does not depend on the metric.
+    pub fn outer(&self, _basis: &Basis, rhs: &Element) -> Result<SimplifiedElement, String> {
+        if !self.0.is_disjoint(&rhs.0) {
+            return Ok(SimplifiedElement::Zero);
+        }
+        let swaps: usize = self
+            .0
+            .iter()
+            .map(|lhs_v| rhs.0.iter().filter(|rhs_v| *rhs_v < lhs_v).count())
+            .sum();
+        let es = Element(self.0.union(&rhs.0).cloned().collect());
+        match swaps % 2 {
+            0 => Ok(SimplifiedElement::Positive(es)),
+            _ => Ok(SimplifiedElement::Negative(es)),
+        }
+    }
+
+    fn complement_vectors(&self, basis: &Basis) -> Element {
+        Element(
+            basis
+                .vectors()
+                .into_iter()
+                .filter(|v| !self.0.contains(v))
+                .collect(),
+        )
+    }
+
+    /// The element that completes this one to the pseudoscalar on the right, `self ^ rc = I`.
+    /// Only the ordering of vectors is used so it is well defined for degenerate bases.
+    pub fn right_complement(&self, basis: &Basis) -> Result<SimplifiedElement, String> {
+        let complement = self.complement_vectors(basis);
+        self.outer(basis, &complement)?
+            .and_then(|_pseudoscalar| Ok(SimplifiedElement::Positive(complement.clone())))
+    }
+
+    /// The element that completes this one to the pseudoscalar on the left, `lc ^ self = I`.
+    pub fn left_complement(&self, basis: &Basis) -> Result<SimplifiedElement, String> {
+        let complement = self.complement_vectors(basis);
+        complement
+            .outer(basis, self)?
+            .and_then(|_pseudoscalar| Ok(SimplifiedElement::Positive(complement.clone())))
+    }
+
+    /// The Hodge dual `~self * I`, which satisfies `a ^ hodge(b) = <a, b> I`. Elements containing
+    /// a zero vector have no Hodge dual in a degenerate basis.
+    pub fn hodge_dual(&self, basis: &Basis) -> Result<SimplifiedElement, String> {
+        if !basis.is_orthonormal() {
+            return Err("The Hodge dual needs an orthonormal basis".to_string());
+        }
+        let mut dual = self.right_complement(basis)?;
+        for v in self.0.iter() {
+            match v.square(basis)? {
+                SquaredElement::Zero => return Ok(SimplifiedElement::Zero),
+                SquaredElement::One => {}
+                SquaredElement::MinusOne => dual = dual.flip(),
+            }
+        }
+        Ok(dual)
+    }
+
+    /// The regressive (anti-wedge) product `lc(rc(a) ^ rc(b))`, built only from complements so it
+    /// does not need the pseudoscalar to be invertible.
+    pub fn regressive(&self, basis: &Basis, rhs: &Element) -> Result<SimplifiedElement, String> {
+        self.right_complement(basis)?
+            .and_then(|lhs| {
+                rhs.right_complement(basis)?
+                    .and_then(|rhs| lhs.outer(basis, &rhs))
+            })?
+            .and_then(|wedge| wedge.left_complement(basis))
+    }
+
+    /// The geometric product restricted to a single grade, an empty grade gives zero. Basis
+    /// elements are blades so this is enough to define the inner products in any metric.
+    fn multiply_to_grade(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+        grade: Option<Grade>,
+    ) -> Result<SimplifiedElement, String> {
+        match grade {
+            Some(grade) => Ok(self.multiply(basis, rhs)?.project(|g| g == grade)),
+            None => Ok(SimplifiedElement::Zero),
+        }
+    }
+
+    pub fn left_contraction(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+    ) -> Result<SimplifiedElement, String> {
+        self.multiply_to_grade(basis, rhs, rhs.grade().checked_sub(self.grade()))
+    }
+
+    pub fn right_contraction(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+    ) -> Result<SimplifiedElement, String> {
+        self.multiply_to_grade(basis, rhs, self.grade().checked_sub(rhs.grade()))
+    }
+
+    pub fn scalar_product(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+    ) -> Result<SimplifiedElement, String> {
+        self.multiply_to_grade(basis, rhs, Some(0))
+    }
+
+    pub fn fat_dot(&self, basis: &Basis, rhs: &Element) -> Result<SimplifiedElement, String> {
+        let grade = if self.grade() > rhs.grade() {
+            self.grade() - rhs.grade()
+        } else {
+            rhs.grade() - self.grade()
+        };
+        self.multiply_to_grade(basis, rhs, Some(grade))
+    }
+
+    /// The fat dot product except that it vanishes when either side is a scalar.
+    pub fn hestenes_inner(
+        &self,
+        basis: &Basis,
+        rhs: &Element,
+    ) -> Result<SimplifiedElement, String> {
+        if self.0.is_empty() || rhs.0.is_empty() {
+            Ok(SimplifiedElement::Zero)
+        } else {
+            self.fat_dot(basis, rhs)
+        }
+    }
+}
+
+/// Written like `e12`, with `e` before every index like `e1e10` once any index has two digits,
+/// and `1` for the scalar. See `display` for names that read back in a given basis.
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "1");
+        }
+        let compact = self.0.iter().all(|v| v.0 < 10);
+        write_indices(f, self.0.iter(), compact)
+    }
+}
+
+/// An element written as its blade in a basis, see `Element::display`.
+pub struct ElementDisplay<'a> {
+    elem: &'a Element,
+    basis: &'a Basis,
+}
+
+impl Element {
+    /// The name of the element's blade as `parse::try_parse_basis_element` reads it, like
+    /// `Basis::element_name` but compact as `e031` in bases of up to ten indexed vectors. The
+    /// element is minus the blade when its orientation is odd.
+    pub fn display<'a>(&'a self, basis: &'a Basis) -> ElementDisplay<'a> {
+        ElementDisplay { elem: self, basis }
     }
 }
 
+impl std::fmt::Display for ElementDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let basis = self.basis;
+        if self.elem.0.is_empty() || basis.names.is_some() || !basis.blade_names.is_empty() {
+            return write!(f, "{}", basis.element_name(self.elem));
+        }
+        let blade = basis.orientation(self.elem).0;
+        write_indices(f, blade.iter(), basis.dimension() <= 10)
+    }
+}
+
+fn write_indices<'a>(
+    f: &mut std::fmt::Formatter,
+    vs: impl Iterator<Item = &'a Vector>,
+    compact: bool,
+) -> std::fmt::Result {
+    for (idx, v) in vs.enumerate() {
+        if idx == 0 || !compact {
+            write!(f, "e")?;
+        }
+        write!(f, "{}", v.0)?;
+    }
+    Ok(())
+}
+
 impl From<Vector> for Element {
     fn from(v: Vector) -> Element {
         Element(vec![v].into_iter().collect())
@@ -120,12 +447,9 @@ impl From<Vector> for Element {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basis::Metric;
 
-    const ONETWOONE: Basis = Basis {
-        zero: 1,
-        positive: 2,
-        negative: 1,
-    };
+    const ONETWOONE: Basis = Basis::new(1, 2, 1);
 
     #[test]
     fn test_bivector_squares_to_minus_one() -> Result<(), String> {
@@ -163,4 +487,303 @@ mod tests {
             _ => Err("Could not construct bivectors".to_string()),
         }
     }
+
+    #[test]
+    fn test_outer_ignores_degenerate_metric() -> Result<(), String> {
+        let e0: Element = Vector(0).into();
+        let e1: Element = Vector(1).into();
+        let e01 = Element(vec![Vector(0), Vector(1)].into_iter().collect());
+
+        assert_eq!(
+            e0.outer(&ONETWOONE, &e1)?,
+            SimplifiedElement::Positive(e01.clone())
+        );
+        assert_eq!(e1.outer(&ONETWOONE, &e0)?, SimplifiedElement::Negative(e01));
+        assert_eq!(e0.outer(&ONETWOONE, &e0)?, SimplifiedElement::Zero);
+        Ok(())
+    }
+
+    fn element(vs: &[usize]) -> Element {
+        Element(vs.iter().cloned().map(Vector).collect())
+    }
+
+    #[test]
+    fn test_contractions_in_g3() -> Result<(), String> {
+        let g3 = Basis::new(0, 3, 0);
+        let (e1, e2, e12) = (element(&[0]), element(&[1]), element(&[0, 1]));
+
+        assert_eq!(
+            e1.left_contraction(&g3, &e12)?,
+            SimplifiedElement::Positive(e2.clone())
+        );
+        assert_eq!(
+            e2.left_contraction(&g3, &e12)?,
+            SimplifiedElement::Negative(e1.clone())
+        );
+        assert_eq!(e12.left_contraction(&g3, &e1)?, SimplifiedElement::Zero);
+        assert_eq!(
+            e12.right_contraction(&g3, &e2)?,
+            SimplifiedElement::Positive(e1.clone())
+        );
+        assert_eq!(
+            e12.right_contraction(&g3, &e1)?,
+            SimplifiedElement::Negative(e2.clone())
+        );
+        assert_eq!(e1.right_contraction(&g3, &e12)?, SimplifiedElement::Zero);
+        assert_eq!(
+            e12.scalar_product(&g3, &e12)?,
+            SimplifiedElement::Negative(element(&[]))
+        );
+        assert_eq!(e1.scalar_product(&g3, &e12)?, SimplifiedElement::Zero);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_products_with_scalars() -> Result<(), String> {
+        let scalar = element(&[]);
+        let e12 = element(&[1, 2]);
+
+        assert_eq!(
+            scalar.fat_dot(&ONETWOONE, &e12)?,
+            SimplifiedElement::Positive(e12.clone())
+        );
+        assert_eq!(
+            e12.fat_dot(&ONETWOONE, &scalar)?,
+            SimplifiedElement::Positive(e12.clone())
+        );
+        assert_eq!(
+            scalar.hestenes_inner(&ONETWOONE, &e12)?,
+            SimplifiedElement::Zero
+        );
+        assert_eq!(
+            element(&[1]).hestenes_inner(&ONETWOONE, &e12)?,
+            SimplifiedElement::Positive(element(&[2]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_contractions_with_zero_vector() -> Result<(), String> {
+        let (e0, e1, e01) = (element(&[0]), element(&[1]), element(&[0, 1]));
+
+        // e0 is degenerate so it annihilates anything containing it
+        assert_eq!(
+            e0.left_contraction(&ONETWOONE, &e01)?,
+            SimplifiedElement::Zero
+        );
+        assert_eq!(e0.scalar_product(&ONETWOONE, &e0)?, SimplifiedElement::Zero);
+        assert_eq!(
+            e1.left_contraction(&ONETWOONE, &e01)?,
+            SimplifiedElement::Negative(e0.clone())
+        );
+        assert_eq!(
+            e01.right_contraction(&ONETWOONE, &e1)?,
+            SimplifiedElement::Positive(e0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_complements_complete_pseudoscalar() -> Result<(), String> {
+        let pseudoscalar = element(&[0, 1, 2, 3]);
+
+        for elem in ONETWOONE.elements() {
+            let right = elem
+                .right_complement(&ONETWOONE)?
+                .and_then(|rc| elem.outer(&ONETWOONE, &rc))?;
+            let left = elem
+                .left_complement(&ONETWOONE)?
+                .and_then(|lc| lc.outer(&ONETWOONE, &elem))?;
+
+            assert_eq!(right, SimplifiedElement::Positive(pseudoscalar.clone()));
+            assert_eq!(left, SimplifiedElement::Positive(pseudoscalar.clone()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_regressive_in_degenerate_basis() -> Result<(), String> {
+        // Pseudoscalar is the identity, even though it squares to zero
+        let pseudoscalar = element(&[0, 1, 2, 3]);
+        for elem in ONETWOONE.elements() {
+            assert_eq!(
+                pseudoscalar.regressive(&ONETWOONE, &elem)?,
+                SimplifiedElement::Positive(elem.clone())
+            );
+        }
+
+        // e012 v e013 = e01 and e013 v e012 = -e01
+        assert_eq!(
+            element(&[0, 1, 2]).regressive(&ONETWOONE, &element(&[0, 1, 3]))?,
+            SimplifiedElement::Positive(element(&[0, 1]))
+        );
+        assert_eq!(
+            element(&[0, 1, 3]).regressive(&ONETWOONE, &element(&[0, 1, 2]))?,
+            SimplifiedElement::Negative(element(&[0, 1]))
+        );
+        assert_eq!(
+            element(&[0, 1]).regressive(&ONETWOONE, &element(&[2, 3]))?,
+            SimplifiedElement::Positive(element(&[]))
+        );
+        assert_eq!(
+            element(&[0]).regressive(&ONETWOONE, &element(&[1, 2, 3]))?,
+            SimplifiedElement::Positive(element(&[]))
+        );
+        assert_eq!(
+            element(&[0, 1]).regressive(&ONETWOONE, &element(&[2]))?,
+            SimplifiedElement::Zero
+        );
+        Ok(())
+    }
+
+    fn signed(sign: isize, vs: &[usize]) -> SimplifiedElement {
+        match sign {
+            1 => SimplifiedElement::Positive(element(vs)),
+            -1 => SimplifiedElement::Negative(element(vs)),
+            _ => SimplifiedElement::Zero,
+        }
+    }
+
+    /// Sign conventions for PGA3, with e0 degenerate. The right complement is the Poincare dual,
+    /// it agrees with the usual e0 -> e123, e1 -> e032, e2 -> e013, e3 -> e021, e01 -> e23,
+    /// e02 -> e31, e03 -> e12 tables and the left complement undoes it.
+    #[test]
+    fn test_pga3_duality_table() -> Result<(), String> {
+        let pga3 = Basis::new(1, 3, 0);
+
+        #[allow(clippy::type_complexity)]
+        let table: &[(
+            &[usize],
+            (isize, &[usize]),
+            (isize, &[usize]),
+            (isize, &[usize]),
+        )] = &[
+            (
+                &[],
+                (1, &[0, 1, 2, 3]),
+                (1, &[0, 1, 2, 3]),
+                (1, &[0, 1, 2, 3]),
+            ),
+            (&[0], (1, &[1, 2, 3]), (-1, &[1, 2, 3]), (0, &[])),
+            (&[1], (-1, &[0, 2, 3]), (1, &[0, 2, 3]), (-1, &[0, 2, 3])),
+            (&[2], (1, &[0, 1, 3]), (-1, &[0, 1, 3]), (1, &[0, 1, 3])),
+            (&[3], (-1, &[0, 1, 2]), (1, &[0, 1, 2]), (-1, &[0, 1, 2])),
+            (&[0, 1], (1, &[2, 3]), (1, &[2, 3]), (0, &[])),
+            (&[0, 2], (-1, &[1, 3]), (-1, &[1, 3]), (0, &[])),
+            (&[0, 3], (1, &[1, 2]), (1, &[1, 2]), (0, &[])),
+            (&[1, 2], (1, &[0, 3]), (1, &[0, 3]), (1, &[0, 3])),
+            (&[1, 3], (-1, &[0, 2]), (-1, &[0, 2]), (-1, &[0, 2])),
+            (&[2, 3], (1, &[0, 1]), (1, &[0, 1]), (1, &[0, 1])),
+            (&[0, 1, 2], (1, &[3]), (-1, &[3]), (0, &[])),
+            (&[0, 1, 3], (-1, &[2]), (1, &[2]), (0, &[])),
+            (&[0, 2, 3], (1, &[1]), (-1, &[1]), (0, &[])),
+            (&[1, 2, 3], (-1, &[0]), (1, &[0]), (-1, &[0])),
+            (&[0, 1, 2, 3], (1, &[]), (1, &[]), (0, &[])),
+        ];
+
+        for (elem, rc, lc, hodge) in table.iter() {
+            let elem = element(elem);
+            assert_eq!(elem.right_complement(&pga3)?, signed(rc.0, rc.1));
+            assert_eq!(elem.left_complement(&pga3)?, signed(lc.0, lc.1));
+            assert_eq!(elem.hodge_dual(&pga3)?, signed(hodge.0, hodge.1));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_hodge_dual_in_sta() -> Result<(), String> {
+        // In STA the Hodge dual picks up the sign of each negative vector
+        let sta = Basis::new(0, 1, 3);
+        let pseudoscalar = element(&[0, 1, 2, 3]);
+
+        assert_eq!(element(&[0]).hodge_dual(&sta)?, signed(1, &[1, 2, 3]));
+        assert_eq!(element(&[2]).hodge_dual(&sta)?, signed(-1, &[0, 1, 3]));
+        assert_eq!(element(&[1, 3]).hodge_dual(&sta)?, signed(-1, &[0, 2]));
+        assert_eq!(pseudoscalar.hodge_dual(&sta)?, signed(-1, &[]));
+        assert_eq!(pseudoscalar.multiply(&sta, &pseudoscalar)?, signed(-1, &[]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_general_metric_agrees_with_signature() -> Result<(), String> {
+        let sta = Basis::new(1, 1, 2);
+        for lhs in sta.elements() {
+            for rhs in sta.elements() {
+                assert_eq!(
+                    SimplifiedElement::from_terms(lhs.metric_multiply(&sta, &rhs)?),
+                    lhs.multiply(&sta, &rhs)?
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_products_in_symbolic_metric() -> Result<(), String> {
+        // e0 . e0 = a, e1 . e1 = b, e0 . e1 = c
+        let (a, b, c) = (
+            Symbols::symbol("a"),
+            Symbols::symbol("b"),
+            Symbols::symbol("c"),
+        );
+        let basis = Basis::from_metric(Metric::new(vec![
+            vec![a.clone(), c.clone()],
+            vec![c.clone(), b.clone()],
+        ])?);
+        let one = Symbols::constant(lift_integer(1));
+        let terms = |ts: Vec<(&[usize], Symbols)>| {
+            SimplifiedElement::from_terms(ts.into_iter().map(|(vs, c)| (element(vs), c)).collect())
+        };
+
+        assert_eq!(
+            element(&[0]).multiply(&basis, &element(&[1]))?,
+            terms(vec![(&[], c.clone()), (&[0, 1], one.clone())])
+        );
+        assert_eq!(
+            element(&[1]).multiply(&basis, &element(&[0]))?,
+            terms(vec![(&[], c.clone()), (&[0, 1], one.invert())])
+        );
+        assert_eq!(
+            element(&[0, 1]).multiply(&basis, &element(&[0, 1]))?,
+            terms(vec![(&[], &c * &c + (&a * &b).invert())])
+        );
+        assert_eq!(
+            element(&[0]).left_contraction(&basis, &element(&[0, 1]))?,
+            terms(vec![(&[0], c.invert()), (&[1], a.clone())])
+        );
+        assert_eq!(
+            element(&[0]).outer(&basis, &element(&[1]))?,
+            signed(1, &[0, 1])
+        );
+        assert!(element(&[0]).hodge_dual(&basis).is_err());
+        assert!(Metric::new(vec![vec![a.clone(), c], vec![b, a]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_outer_shared_vector_is_zero() -> Result<(), String> {
+        let e12 = Element(vec![Vector(1), Vector(2)].into_iter().collect());
+        let e23 = Element(vec![Vector(2), Vector(3)].into_iter().collect());
+
+        assert_eq!(e12.outer(&ONETWOONE, &e23)?, SimplifiedElement::Zero);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(element(&[]).to_string(), "1");
+        assert_eq!(element(&[1, 2]).to_string(), "e12");
+        assert_eq!(element(&[0, 2, 3]).to_string(), "e023");
+        assert_eq!(element(&[1, 10]).to_string(), "e1e10");
+
+        let oriented = ONETWOONE
+            .clone()
+            .with_orientations(vec![vec![Vector(3), Vector(1)]])
+            .unwrap();
+        assert_eq!(element(&[1, 3]).display(&oriented).to_string(), "e31");
+        assert_eq!(element(&[]).display(&oriented).to_string(), "1");
+        // Eleven vectors read `e12` as e12, not e1 e2
+        let eleven = Basis::new(0, 11, 0);
+        assert_eq!(element(&[1, 2]).display(&eleven).to_string(), "e1e2");
+    }
 }
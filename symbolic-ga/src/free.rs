@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::basis::Basis;
+use crate::element::Element;
+use crate::multivector::MultiVector;
+use crate::symbols::{lift_integer, Symbols};
+
+/// A generic multivector symbol with the involutions applied to it. Reverse and grade
+/// involution commute and are their own inverses, so two flags cover every combination.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Letter {
+    pub name: String,
+    pub reversed: bool,
+    pub involuted: bool,
+}
+
+/// A product of generic symbols in order, the empty word is the scalar 1.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Word(pub Vec<Letter>);
+
+/// Sums of products of multivector valued symbols which do not commute, with commuting scalar
+/// coefficients. Nothing is known about the symbols so the only rewriting is from the
+/// involutions, `~(AB) = ~B ~A` and `(AB)^ = A^ B^`, which gives a normal form.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FreeMultiVector(pub BTreeMap<Word, Symbols>);
+
+/// The outcome of `FreeMultiVector::check_identity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Identity {
+    /// Both sides have the same normal form, so they are equal in every algebra.
+    Holds,
+    /// Equal once every symbol is expanded to a general multivector of the basis.
+    HoldsInBasis,
+    Fails,
+}
+
+impl FreeMultiVector {
+    pub fn symbol(name: &str) -> FreeMultiVector {
+        let letter = Letter {
+            name: name.to_string(),
+            reversed: false,
+            involuted: false,
+        };
+        let one = Symbols::constant(lift_integer(1));
+        FreeMultiVector(std::iter::once((Word(vec![letter]), one)).collect())
+    }
+
+    pub fn scalar(sym: Symbols) -> FreeMultiVector {
+        FreeMultiVector::default()
+            + FreeMultiVector(std::iter::once((Word::default(), sym)).collect())
+    }
+
+    pub fn multiply(&self, rhs: &FreeMultiVector) -> FreeMultiVector {
+        let mut result = FreeMultiVector::default();
+        for (lhs_word, lhs_sym) in self.0.iter() {
+            for (rhs_word, rhs_sym) in rhs.0.iter() {
+                let word = Word(
+                    lhs_word
+                        .0
+                        .iter()
+                        .chain(rhs_word.0.iter())
+                        .cloned()
+                        .collect(),
+                );
+                result =
+                    result + FreeMultiVector(std::iter::once((word, lhs_sym * rhs_sym)).collect());
+            }
+        }
+        result
+    }
+
+    pub fn scale_symbols(&self, factor: &Symbols) -> FreeMultiVector {
+        FreeMultiVector(
+            self.0
+                .iter()
+                .map(|(word, sym)| (word.clone(), sym * factor))
+                .filter(|(_word, sym)| !sym.is_zero())
+                .collect(),
+        )
+    }
+
+    /// Reverses the order of every product and reverses each symbol.
+    pub fn reverse(&self) -> FreeMultiVector {
+        self.map_words(|word| {
+            Word(
+                word.0
+                    .iter()
+                    .rev()
+                    .map(|letter| Letter {
+                        reversed: !letter.reversed,
+                        ..letter.clone()
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    /// Involutes each symbol, keeping the order of products.
+    pub fn grade_involution(&self) -> FreeMultiVector {
+        self.map_words(|word| {
+            Word(
+                word.0
+                    .iter()
+                    .map(|letter| Letter {
+                        involuted: !letter.involuted,
+                        ..letter.clone()
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    pub fn clifford_conjugate(&self) -> FreeMultiVector {
+        self.reverse().grade_involution()
+    }
+
+    /// Replaces every symbol `X` by a general multivector of the basis with coefficients named
+    /// `X_1 + X_e0 e0 + ... + X_e01 e01 + ...`.
+    pub fn expand(&self, basis: &Basis) -> Result<MultiVector, String> {
+        let mut result = MultiVector::default();
+        for (word, sym) in self.0.iter() {
+            let mut product = scalar_mv(sym.clone());
+            for letter in word.0.iter() {
+                product = product.multiply(basis, &general_multivector(basis, letter))?;
+            }
+            result = result + product;
+        }
+        Ok(result)
+    }
+
+    /// Compares normal forms, which proves an identity for every algebra. Different normal
+    /// forms can still agree in a particular algebra, for example a scalar part like
+    /// `A + ~A + A^ + ~A^ = 4 <A>` commutes with everything, so those are expanded in the basis.
+    pub fn check_identity(
+        basis: &Basis,
+        lhs: &FreeMultiVector,
+        rhs: &FreeMultiVector,
+    ) -> Result<Identity, String> {
+        if lhs == rhs {
+            return Ok(Identity::Holds);
+        }
+        let difference = lhs.clone() + rhs.scale_symbols(&Symbols::constant(lift_integer(-1)));
+        if difference.expand(basis)?.0.is_empty() {
+            Ok(Identity::HoldsInBasis)
+        } else {
+            Ok(Identity::Fails)
+        }
+    }
+
+    fn map_words(&self, f: impl Fn(&Word) -> Word) -> FreeMultiVector {
+        self.0
+            .iter()
+            .map(|(word, sym)| FreeMultiVector(std::iter::once((f(word), sym.clone())).collect()))
+            .fold(FreeMultiVector::default(), |prev, term| prev + term)
+    }
+}
+
+fn general_multivector(basis: &Basis, letter: &Letter) -> MultiVector {
+    let mv = MultiVector(
+        basis
+            .elements()
+            .into_iter()
+            .map(|elem| {
+                let name = match elem.0.is_empty() {
+                    true => format!("{}_1", letter.name),
+                    false => format!("{}_{}", letter.name, elem),
+                };
+                (elem, Symbols::symbol(&name))
+            })
+            .collect(),
+    );
+    let mv = if letter.reversed { mv.reverse() } else { mv };
+    if letter.involuted {
+        mv.grade_involution()
+    } else {
+        mv
+    }
+}
+
+fn scalar_mv(sym: Symbols) -> MultiVector {
+    MultiVector::default() + MultiVector(std::iter::once((Element(BTreeSet::new()), sym)).collect())
+}
+
+impl std::ops::Add for FreeMultiVector {
+    type Output = FreeMultiVector;
+
+    fn add(self, rhs: FreeMultiVector) -> FreeMultiVector {
+        self.0
+            .into_iter()
+            .chain(rhs.0)
+            .fold(FreeMultiVector::default(), |mut prev, (word, sym)| {
+                let existing = prev.0.remove(&word).unwrap_or_else(Symbols::zero);
+                let sum = existing + sym;
+                if !sum.is_zero() {
+                    prev.0.insert(word, sum);
+                }
+                prev
+            })
+    }
+}
+
+/// Symbols with involutions are written like the `ga!` functions, `(2*a)reverse(B) A`.
+impl std::fmt::Display for FreeMultiVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        for (idx, (word, sym)) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "({})", sym)?;
+            let letters: Vec<String> = word
+                .0
+                .iter()
+                .map(|letter| match (letter.reversed, letter.involuted) {
+                    (false, false) => letter.name.clone(),
+                    (true, false) => format!("reverse({})", letter.name),
+                    (false, true) => format!("involute({})", letter.name),
+                    (true, true) => format!("conjugate({})", letter.name),
+                })
+                .collect();
+            write!(f, "{}", letters.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G3: Basis = Basis::new(0, 3, 0);
+
+    fn sym(name: &str) -> FreeMultiVector {
+        FreeMultiVector::symbol(name)
+    }
+
+    #[test]
+    fn test_involutions_of_products() -> Result<(), String> {
+        let (a, b, c) = (sym("A"), sym("B"), sym("C"));
+        let abc = a.multiply(&b).multiply(&c);
+
+        // ~(ABC) = ~C ~B ~A
+        let reversed = c.reverse().multiply(&b.reverse()).multiply(&a.reverse());
+        assert_eq!(abc.reverse(), reversed);
+        assert_eq!(abc.reverse().reverse(), abc);
+        assert_eq!(
+            abc.clifford_conjugate(),
+            c.clifford_conjugate()
+                .multiply(&b.clifford_conjugate())
+                .multiply(&a.clifford_conjugate())
+        );
+        assert_eq!(
+            FreeMultiVector::check_identity(&G3, &abc.reverse(), &reversed)?,
+            Identity::Holds
+        );
+        assert_eq!(
+            abc.grade_involution().to_string(),
+            "(1)involute(A) involute(B) involute(C)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_identity() -> Result<(), String> {
+        let (a, b) = (sym("A"), sym("B"));
+        let ab = a.multiply(&b);
+        let ba = b.multiply(&a);
+        assert_eq!(
+            FreeMultiVector::check_identity(&G3, &ab, &ba)?,
+            Identity::Fails
+        );
+
+        // The scalar part 4 <A> commutes with B
+        let scalar_part = a.clone() + a.reverse() + a.grade_involution() + a.clifford_conjugate();
+        assert_eq!(
+            FreeMultiVector::check_identity(
+                &G3,
+                &scalar_part.multiply(&b),
+                &b.multiply(&scalar_part)
+            )?,
+            Identity::HoldsInBasis
+        );
+
+        // AB + ~B~A is twice the "self-reverse" part, unchanged by reversing
+        let sum = ab.clone() + ab.reverse();
+        assert_eq!(
+            FreeMultiVector::check_identity(&G3, &sum.reverse(), &sum)?,
+            Identity::Holds
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+/// Real scalar functions that are kept as opaque terms inside `Symbols`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Function {
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    Exp,
+    Ln,
+    /// `if_zero(x, a, b)` is `a` when `x` is zero and `b` otherwise, guarding closed forms
+    /// like `sin(u) / u` at zero. Only built internally, so it is not in `from_name`.
+    IfZero,
+}
+
+const FUNCTIONS: [Function; 16] = [
+    Function::Sqrt,
+    Function::Sin,
+    Function::Cos,
+    Function::Tan,
+    Function::Asin,
+    Function::Acos,
+    Function::Atan,
+    Function::Atan2,
+    Function::Sinh,
+    Function::Cosh,
+    Function::Tanh,
+    Function::Asinh,
+    Function::Acosh,
+    Function::Atanh,
+    Function::Exp,
+    Function::Ln,
+];
+
+impl Function {
+    /// The name of the matching `f32` / `f64` method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Sqrt => "sqrt",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Atan2 => "atan2",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Asinh => "asinh",
+            Function::Acosh => "acosh",
+            Function::Atanh => "atanh",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::IfZero => "if_zero",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Function> {
+        FUNCTIONS.iter().cloned().find(|f| f.name() == name)
+    }
+
+    pub fn arity(&self) -> usize {
+        match self {
+            Function::Atan2 => 2,
+            Function::IfZero => 3,
+            _ => 1,
+        }
+    }
+
+    /// Fails unless `count` arguments match the arity.
+    pub fn check_arity(&self, count: usize) -> Result<(), String> {
+        if count == self.arity() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} function takes {} arguments, given {}",
+                self.name(),
+                self.arity(),
+                count
+            ))
+        }
+    }
+
+    /// The value at floating point arguments, which must match the arity.
+    pub fn evaluate(&self, args: &[f64]) -> f64 {
+        match self {
+            Function::Sqrt => args[0].sqrt(),
+            Function::Sin => args[0].sin(),
+            Function::Cos => args[0].cos(),
+            Function::Tan => args[0].tan(),
+            Function::Asin => args[0].asin(),
+            Function::Acos => args[0].acos(),
+            Function::Atan => args[0].atan(),
+            Function::Atan2 => args[0].atan2(args[1]),
+            Function::Sinh => args[0].sinh(),
+            Function::Cosh => args[0].cosh(),
+            Function::Tanh => args[0].tanh(),
+            Function::Asinh => args[0].asinh(),
+            Function::Acosh => args[0].acosh(),
+            Function::Atanh => args[0].atanh(),
+            Function::Exp => args[0].exp(),
+            Function::Ln => args[0].ln(),
+            Function::IfZero if args[0] == 0.0 => args[1],
+            Function::IfZero => args[2],
+        }
+    }
+
+    /// Whether the function never takes negative values.
+    pub fn is_nonnegative(&self) -> bool {
+        matches!(self, Function::Sqrt | Function::Cosh | Function::Exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for function in FUNCTIONS.iter() {
+            assert_eq!(Function::from_name(function.name()), Some(*function));
+        }
+        assert_eq!(Function::from_name("log"), None);
+        assert_eq!(Function::from_name("if_zero"), None);
+    }
+
+    #[test]
+    fn test_if_zero_picks_a_branch() {
+        assert_eq!(Function::IfZero.evaluate(&[0.0, 1.0, f64::NAN]), 1.0);
+        assert_eq!(Function::IfZero.evaluate(&[0.5, f64::NAN, 2.0]), 2.0);
+    }
+}
@@ -0,0 +1,266 @@
+use num::rational::BigRational;
+use num::{One, Signed};
+
+use crate::element::Element;
+use crate::function::Function;
+use crate::multivector::MultiVector;
+use crate::polynomial::{Polynomial, Symbol};
+use crate::symbols::Symbols;
+
+/// How basis blades are written.
+#[derive(Debug, Clone)]
+pub enum BasisNames {
+    /// A letter with the vector indices as one subscript, like `e_{12}`.
+    Indexed(String),
+    /// A name for every basis vector, written side by side for blades like
+    /// `\gamma_{0} \gamma_{1}`. Vectors without a name fall back to `e_{i}`.
+    Vectors(Vec<String>),
+}
+
+/// Renders symbols and multivectors as LaTeX math.
+#[derive(Debug, Clone)]
+pub struct Latex {
+    pub names: BasisNames,
+}
+
+impl Default for Latex {
+    fn default() -> Latex {
+        Latex {
+            names: BasisNames::Indexed("e".to_string()),
+        }
+    }
+}
+
+impl Latex {
+    pub fn new(names: BasisNames) -> Latex {
+        Latex { names }
+    }
+
+    pub fn element(&self, elem: &Element) -> String {
+        if elem.0.is_empty() {
+            return "1".to_string();
+        }
+        match &self.names {
+            BasisNames::Indexed(letter) => {
+                let indices: Vec<String> = elem.0.iter().map(|v| v.0.to_string()).collect();
+                let separator = if elem.0.iter().all(|v| v.0 < 10) {
+                    ""
+                } else {
+                    ","
+                };
+                format!("{}_{{{}}}", letter, indices.join(separator))
+            }
+            BasisNames::Vectors(names) => elem
+                .0
+                .iter()
+                .map(|v| {
+                    names
+                        .get(v.0)
+                        .cloned()
+                        .unwrap_or_else(|| format!("e_{{{}}}", v.0))
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
+    /// Quotients become `\frac{}{}`.
+    pub fn symbols(&self, syms: &Symbols) -> String {
+        if syms.is_polynomial() {
+            return self.polynomial(&syms.numer);
+        }
+        let numer = &syms.numer;
+        let negative = numer.0.len() == 1 && numer.0.values().all(|scale| scale.is_negative());
+        let numer = if negative {
+            numer.scale(&BigRational::from_integer((-1).into()))
+        } else {
+            numer.clone()
+        };
+        format!(
+            "{}\\frac{{{}}}{{{}}}",
+            if negative { "-" } else { "" },
+            self.polynomial(&numer),
+            self.polynomial(&syms.denom)
+        )
+    }
+
+    pub fn polynomial(&self, poly: &Polynomial) -> String {
+        if poly.is_zero() {
+            return "0".to_string();
+        }
+        let mut out = String::new();
+        for (idx, (powers, scale)) in poly.terms().into_iter().enumerate() {
+            out += match (idx, scale.is_negative()) {
+                (0, true) => "-",
+                (0, false) => "",
+                (_, true) => " - ",
+                (_, false) => " + ",
+            };
+            let scale = scale.abs();
+            if powers.is_empty() || !scale.is_one() {
+                out += &rational(&scale);
+            }
+            for (sym, pow) in powers.iter() {
+                if !out.is_empty() && !out.ends_with(' ') && !out.ends_with('-') {
+                    out += " ";
+                }
+                out += &self.symbol(sym);
+                if *pow > 1 {
+                    out += &format!("^{{{}}}", pow);
+                }
+            }
+        }
+        out
+    }
+
+    /// Names like `a_e12` get the part after the first underscore as a subscript.
+    pub fn symbol(&self, sym: &Symbol) -> String {
+        match sym {
+            Symbol::Named(name) => match name.split_once('_') {
+                Some((base, subscript)) => format!("{}_{{{}}}", base, subscript),
+                None => name.clone(),
+            },
+            Symbol::Apply(Function::Sqrt, args) => format!("\\sqrt{{{}}}", self.symbols(&args[0])),
+            Symbol::Apply(Function::IfZero, args) => format!(
+                "\\begin{{cases}} {} & {} = 0 \\\\ {} & \\text{{otherwise}} \\end{{cases}}",
+                self.symbols(&args[1]),
+                self.symbols(&args[0]),
+                self.symbols(&args[2])
+            ),
+            Symbol::Apply(function, args) => {
+                let args: Vec<String> = args.iter().map(|arg| self.symbols(arg)).collect();
+                format!(
+                    "{}\\left({}\\right)",
+                    function_name(*function),
+                    args.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Components by grade, bracketed when they have more than one term.
+    pub fn multivector(&self, mv: &MultiVector) -> String {
+        if mv.0.is_empty() {
+            return "0".to_string();
+        }
+        let mut out = String::new();
+        for (idx, (elem, sym)) in mv.by_grade().into_iter().enumerate() {
+            let single = sym.is_polynomial() && sym.numer.0.len() == 1;
+            let negative = single && sym.numer.0.values().all(|scale| scale.is_negative());
+            let sym = if negative { sym.invert() } else { sym.clone() };
+            out += match (idx, negative) {
+                (0, true) => "-",
+                (0, false) => "",
+                (_, true) => " - ",
+                (_, false) => " + ",
+            };
+
+            let value = self.symbols(&sym);
+            match (elem.0.is_empty(), single) {
+                (true, _) => out += &value,
+                (false, true) if sym.constant_value().is_some_and(|c| c.is_one()) => {}
+                (false, true) => out += &format!("{} ", value),
+                (false, false) => out += &format!("\\left({}\\right) ", value),
+            }
+            if !elem.0.is_empty() {
+                out += &self.element(elem);
+            }
+        }
+        out
+    }
+}
+
+fn rational(value: &BigRational) -> String {
+    if value.is_integer() {
+        value.numer().to_string()
+    } else {
+        format!("\\frac{{{}}}{{{}}}", value.numer(), value.denom())
+    }
+}
+
+fn function_name(function: Function) -> String {
+    match function {
+        Function::Sin => "\\sin",
+        Function::Cos => "\\cos",
+        Function::Tan => "\\tan",
+        Function::Asin => "\\arcsin",
+        Function::Acos => "\\arccos",
+        Function::Atan => "\\arctan",
+        Function::Sinh => "\\sinh",
+        Function::Cosh => "\\cosh",
+        Function::Tanh => "\\tanh",
+        Function::Exp => "\\exp",
+        Function::Ln => "\\ln",
+        function => return format!("\\operatorname{{{}}}", function.name()),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::basis::Vector;
+    use crate::symbols::lift_integer;
+
+    fn element(vs: &[usize]) -> Element {
+        Element(vs.iter().cloned().map(Vector).collect::<BTreeSet<_>>())
+    }
+
+    #[test]
+    fn test_symbols() -> Result<(), String> {
+        let latex = Latex::default();
+        let a = Symbols::symbol("a_e12");
+        let b = Symbols::symbol("b");
+        let half = Symbols::constant(BigRational::new(1.into(), 2.into()));
+
+        assert_eq!(
+            latex.symbols(&(&(&a * &a) * &b.scale(&lift_integer(3)) + half.invert())),
+            "3 a_{e12}^{2} b - \\frac{1}{2}"
+        );
+        assert_eq!(
+            latex.symbols(&(&b.invert() * &(a.clone() + b.clone()).recip()?)),
+            "-\\frac{b}{a_{e12} + b}"
+        );
+        assert_eq!(
+            latex.symbols(&Symbols::apply(
+                Function::Cos,
+                vec![Symbols::apply(Function::Sqrt, vec![b.clone()])]
+            )),
+            "\\cos\\left(\\sqrt{b}\\right)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multivector_with_basis_names() {
+        let b = Symbols::symbol("b");
+        let mv = MultiVector(
+            vec![
+                (element(&[]), Symbols::constant(lift_integer(1))),
+                (
+                    element(&[0, 1]),
+                    b.clone() + Symbols::constant(lift_integer(1)),
+                ),
+                (element(&[1]), b.invert()),
+                (element(&[2]), Symbols::constant(lift_integer(1))),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(
+            Latex::default().multivector(&mv),
+            "1 - b e_{1} + e_{2} + \\left(b + 1\\right) e_{01}"
+        );
+        let gamma = Latex::new(BasisNames::Vectors(vec![
+            "\\gamma_{0}".to_string(),
+            "\\gamma_{1}".to_string(),
+        ]));
+        assert_eq!(
+            gamma.multivector(&mv),
+            "1 - b \\gamma_{1} + e_{2} + \\left(b + 1\\right) \\gamma_{0} \\gamma_{1}"
+        );
+    }
+}
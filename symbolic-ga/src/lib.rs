@@ -1,4 +1,13 @@
 pub mod basis;
+pub mod conformal;
 pub mod element;
+pub mod free;
+pub mod function;
+pub mod latex;
 pub mod multivector;
+pub mod parse;
+pub mod polynomial;
+pub mod relations;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod symbols;
@@ -1,30 +1,495 @@
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
-use crate::basis::{Basis, Grade, SquaredElement};
-use crate::element::Element;
-use crate::symbols::Symbols;
+use num::rational::BigRational;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::basis::{Basis, Grade};
+use crate::element::{Element, SimplifiedElement};
+use crate::function::Function;
+use crate::relations::Relations;
+use crate::symbols::{lift_integer, Symbol, Symbols};
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct MultiVector(pub BTreeMap<Element, Symbols>);
 
 impl MultiVector {
+    /// Reads text like `3*a*e2 + b^2*e1` or the `Display` form, see `parse::parse_multivector`.
+    pub fn parse(basis: &Basis, src: &str) -> Result<MultiVector, String> {
+        crate::parse::parse_multivector(basis, src)
+    }
+
     pub fn multiply(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::multiply)
+    }
+
+    pub fn outer(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::outer)
+    }
+
+    pub fn left_contraction(
+        &self,
+        basis: &Basis,
+        rhs: &MultiVector,
+    ) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::left_contraction)
+    }
+
+    pub fn right_contraction(
+        &self,
+        basis: &Basis,
+        rhs: &MultiVector,
+    ) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::right_contraction)
+    }
+
+    pub fn scalar_product(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::scalar_product)
+    }
+
+    pub fn fat_dot(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::fat_dot)
+    }
+
+    pub fn hestenes_inner(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::hestenes_inner)
+    }
+
+    /// The regressive (anti-wedge) product, dual to `outer` but independent of the metric.
+    pub fn regressive(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.product(basis, rhs, Element::regressive)
+    }
+
+    /// Intersection of subspaces in plane-based PGA, where vectors are planes and trivectors
+    /// are points, so two planes meet in a line.
+    pub fn meet(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.outer(basis, rhs)
+    }
+
+    /// The subspace spanned by both, like the line through two points, see `meet`.
+    pub fn join(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        self.regressive(basis, rhs)
+    }
+
+    pub fn right_complement(&self, basis: &Basis) -> Result<MultiVector, String> {
+        self.map_elements(basis, Element::right_complement)
+    }
+
+    pub fn left_complement(&self, basis: &Basis) -> Result<MultiVector, String> {
+        self.map_elements(basis, Element::left_complement)
+    }
+
+    /// The metric independent dual, which is the right complement. Undo it with `left_complement`.
+    pub fn poincare_dual(&self, basis: &Basis) -> Result<MultiVector, String> {
+        self.right_complement(basis)
+    }
+
+    pub fn hodge_dual(&self, basis: &Basis) -> Result<MultiVector, String> {
+        self.map_elements(basis, Element::hodge_dual)
+    }
+
+    /// The commutator product `(ab - ba) / 2`.
+    pub fn commutator(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        let ab = self.multiply(basis, rhs)?;
+        let ba = rhs.multiply(basis, self)?;
+        Ok((ab + ba.scale(&lift_integer(-1))).scale(&half()))
+    }
+
+    /// The anti-commutator product `(ab + ba) / 2`.
+    pub fn anti_commutator(&self, basis: &Basis, rhs: &MultiVector) -> Result<MultiVector, String> {
+        let ab = self.multiply(basis, rhs)?;
+        let ba = rhs.multiply(basis, self)?;
+        Ok((ab + ba).scale(&half()))
+    }
+
+    /// Applies a versor keeping only the grades present in `x`, see `sandwich_to`.
+    pub fn sandwich(&self, basis: &Basis, x: &MultiVector) -> Result<MultiVector, String> {
+        self.sandwich_to(basis, x, &x.grades())
+    }
+
+    /// Applies a versor as `v x ~v`, where `~v` is the inverse of a normalised versor. When every
+    /// grade of `v` is odd this is `v involute(x) ~v`, which is `involute(v) x ~v` on vectors so
+    /// they reflect, and keeps reflecting the factors of higher grade blades. Only the given
+    /// grades are kept, the others cancel for normalised versors, so they are never expanded.
+    pub fn sandwich_to(
+        &self,
+        basis: &Basis,
+        x: &MultiVector,
+        grades: &BTreeSet<Grade>,
+    ) -> Result<MultiVector, String> {
+        let odd = !self.0.is_empty() && self.grades().iter().all(|g| g % 2 == 1);
+        let x = match odd {
+            true => x.grade_involution(),
+            false => x.clone(),
+        };
+        self.multiply(basis, &x)?
+            .product(basis, &self.reverse(), |lhs, basis, rhs| {
+                Ok(lhs.multiply(basis, rhs)?.project(|g| grades.contains(&g)))
+            })
+    }
+
+    pub fn grades(&self) -> BTreeSet<Grade> {
+        self.0.keys().map(Element::grade).collect()
+    }
+
+    pub fn scale(&self, factor: &BigRational) -> MultiVector {
+        MultiVector(
+            self.0
+                .iter()
+                .map(|(elem, sym)| (elem.clone(), sym.scale(factor)))
+                .filter(|(_elem, sym)| !sym.is_zero())
+                .collect(),
+        )
+    }
+
+    /// Multiplies every coefficient by a scalar expression.
+    pub fn scale_symbols(&self, factor: &Symbols) -> MultiVector {
+        MultiVector(
+            self.0
+                .iter()
+                .map(|(elem, sym)| (elem.clone(), sym * factor))
+                .filter(|(_elem, sym)| !sym.is_zero())
+                .collect(),
+        )
+    }
+
+    /// Reverses the order of vectors in each element, `~(ab) = ~b ~a`.
+    pub fn reverse(&self) -> MultiVector {
+        self.negate_grades(|grade| grade % 4 >= 2)
+    }
+
+    /// Negates every vector, flipping the sign of odd grades.
+    pub fn grade_involution(&self) -> MultiVector {
+        self.negate_grades(|grade| grade % 2 == 1)
+    }
+
+    /// The composition of reverse and grade involution.
+    pub fn clifford_conjugate(&self) -> MultiVector {
+        self.negate_grades(|grade| (grade + 1) % 4 >= 2)
+    }
+
+    /// Components are stored on sorted elements, so products never see the basis orientations.
+    /// This negates the components of odd oriented blades, converting coefficients to or from
+    /// those blades, and is its own inverse.
+    pub fn orient(&self, basis: &Basis) -> MultiVector {
+        MultiVector(
+            self.0
+                .iter()
+                .map(|(elem, sym)| match basis.orientation(elem).1 {
+                    true => (elem.clone(), sym.invert()),
+                    false => (elem.clone(), sym.clone()),
+                })
+                .collect(),
+        )
+    }
+
+    /// The inverse of a versor, `v^-1 = ~v / (v ~v)`. Fails when `v ~v` is not a scalar.
+    pub fn versor_inverse(&self, basis: &Basis) -> Result<MultiVector, String> {
+        let reverse = self.reverse();
+        let norm = self
+            .multiply(basis, &reverse)?
+            .scalar_part()
+            .ok_or_else(|| "Not a versor, v ~v is not a scalar".to_string())?;
+        Ok(reverse.scale_symbols(&norm.recip().map_err(|_| no_inverse())?))
+    }
+
+    /// The inverse as an adjugate divided by a scalar norm. Versors take a fast path, anything
+    /// else uses the closed forms for algebras of up to 5 dimensions.
+    pub fn inverse(&self, basis: &Basis) -> Result<MultiVector, String> {
+        if let Some(scalar) = self.scalar_part() {
+            return Ok(scalar_mv(scalar.recip().map_err(|_| no_inverse())?));
+        }
+        if let Ok(inverse) = self.versor_inverse(basis) {
+            return Ok(inverse);
+        }
+
+        let conjugate = self.clifford_conjugate();
+        let adjugate = match basis.vectors().len() {
+            0..=2 => conjugate,
+            3 => conjugate
+                .multiply(basis, &self.grade_involution())?
+                .multiply(basis, &self.reverse())?,
+            4 => {
+                let norm = self.multiply(basis, &conjugate)?;
+                conjugate.multiply(basis, &norm.negate_grades(|g| g == 3 || g == 4))?
+            }
+            5 => {
+                let partial = conjugate
+                    .multiply(basis, &self.grade_involution())?
+                    .multiply(basis, &self.reverse())?;
+                let norm = self.multiply(basis, &partial)?;
+                partial.multiply(basis, &norm.negate_grades(|g| g == 1 || g == 4))?
+            }
+            n => return Err(format!("No closed form inverse for {} dimensions", n)),
+        };
+
+        let norm = self
+            .multiply(basis, &adjugate)?
+            .scalar_part()
+            .ok_or_else(|| "Closed form inverse did not give a scalar norm".to_string())?;
+        Ok(adjugate.scale_symbols(&norm.recip().map_err(|_| no_inverse())?))
+    }
+
+    /// An integer power by repeated squaring, negative powers are powers of the inverse.
+    pub fn pow(&self, basis: &Basis, n: isize) -> Result<MultiVector, String> {
+        let mut base = if n < 0 {
+            self.inverse(basis)?
+        } else {
+            self.clone()
+        };
+        let mut result = scalar_mv(one());
+        let mut n = n.unsigned_abs();
+        while n > 0 {
+            if n % 2 == 1 {
+                result = result.multiply(basis, &base)?;
+            }
+            n /= 2;
+            if n > 0 {
+                base = base.multiply(basis, &base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// The exponential of a scalar plus a bivector in closed form, the scalar commutes so it
+    /// just scales `exp(B)`. When `B B` is a scalar the result uses `cos`/`sin`, `1 + B` or
+    /// `cosh`/`sinh` depending on its sign. When `B B` also has a null part commuting with `B`,
+    /// as for a general PGA3 bivector, `B` is split into commuting simple parts.
+    pub fn exp(&self, basis: &Basis) -> Result<MultiVector, String> {
+        if self.grades().iter().any(|grade| *grade != 0 && *grade != 2) {
+            return Err("exp is only defined for scalars and bivectors".to_string());
+        }
+
+        let scalar = scalar_component(self);
+        let bivector = self.project(basis, &vec![2].into_iter().collect());
+        let scale = Symbols::apply(Function::Exp, vec![scalar]);
+        Ok(bivector.exp_bivector(basis)?.scale_symbols(&scale))
+    }
+
+    fn exp_bivector(&self, basis: &Basis) -> Result<MultiVector, String> {
+        if self.0.is_empty() {
+            return Ok(scalar_mv(one()));
+        }
+
+        let square = self.multiply(basis, self)?;
+        let scalar = scalar_component(&square);
+        let rest = square + scalar_mv(scalar.invert());
+        if rest.0.is_empty() {
+            return exp_simple(self, &scalar);
+        }
+        self.check_null_split(basis, &rest)?;
+
+        // B = Br (1 + r / 2s) where Br Br = s, so exp(B) = exp(Br) (1 + Br r / 2s)
+        let ideal = rest.scale_symbols(&scalar.recip()?.scale(&half()));
+        let real = self.clone() + self.multiply(basis, &ideal)?.scale(&lift_integer(-1));
+        let ideal = real.multiply(basis, &ideal)?;
+        let split = exp_simple(&real, &scalar)?.multiply(basis, &(scalar_mv(one()) + ideal))?;
+
+        // With s = 0 the series ends, B B = r and r r = 0, as for a pure translation
+        let cubed = self.multiply(basis, &rest)?;
+        let series = scalar_mv(one())
+            + self.clone()
+            + rest.scale(&half())
+            + cubed.scale(&BigRational::new(1.into(), 6.into()));
+        Ok(if_zero(&scalar, &series, &split))
+    }
+
+    /// The natural logarithm of a scalar, or of a normalized even versor made of scalar,
+    /// bivector and null 4-vector parts, the inverse of `exp` for rotations up to half a turn.
+    pub fn log(&self, basis: &Basis) -> Result<MultiVector, String> {
+        if self.grades().iter().any(|grade| ![0, 2, 4].contains(grade)) {
+            return Err("log is only defined for scalar, bivector and 4-vector parts".to_string());
+        }
+
+        let scalar = scalar_component(self);
+        if self.grades().iter().all(|grade| *grade == 0) {
+            return Ok(scalar_mv(Symbols::apply(Function::Ln, vec![scalar])));
+        }
+        let bivector = self.project(basis, &vec![2].into_iter().collect());
+        let rest = self.project(basis, &vec![4].into_iter().collect());
+        if !rest.0.is_empty() {
+            bivector.check_null_split(basis, &rest)?;
+        }
+        let square = scalar_component(&bivector.multiply(basis, &bivector)?);
+
+        match (square.definite_sign(), rest.0.is_empty()) {
+            (Some(Ordering::Equal), true) => Ok(bivector.scale_symbols(&scalar.recip()?)),
+            (Some(Ordering::Less), _) => {
+                // M = cos u + sin u / u Br (1 + t I) + ..., where sin u = w
+                let w_squared = square.invert();
+                let w = Symbols::apply(Function::Sqrt, vec![w_squared.clone()]);
+                let u = Symbols::apply(Function::Atan2, vec![w.clone(), scalar.clone()]);
+                let real = bivector.scale_symbols(&(&u * &w.recip()?));
+                let ratio = &(&u * &scalar) * &w.recip()?;
+                let ideal_scale = &(ratio + one().invert()) * &w_squared.recip()?;
+                let ideal = bivector.multiply(basis, &rest)?;
+                let split = real + ideal.scale_symbols(&ideal_scale);
+
+                // The limit as w goes to zero, u / w -> 1 / s and the ideal scale -> -1 / 3ss
+                let s_recip = scalar.recip()?;
+                let limit_scale =
+                    (&s_recip * &s_recip).scale(&BigRational::new((-1).into(), 3.into()));
+                let limit = bivector.scale_symbols(&s_recip) + ideal.scale_symbols(&limit_scale);
+                Ok(if_zero(&w_squared, &limit, &split))
+            }
+            (Some(Ordering::Greater), true) => {
+                let w = Symbols::apply(Function::Sqrt, vec![square.clone()]);
+                let u = Symbols::apply(Function::Atanh, vec![&w * &scalar.recip()?]);
+                let split = bivector.scale_symbols(&(&u * &w.recip()?));
+                let limit = bivector.scale_symbols(&scalar.recip()?);
+                Ok(if_zero(&square, &limit, &split))
+            }
+            (Some(_), false) => Err("log needs a Euclidean bivector part to split".to_string()),
+            (None, _) => Err("Cannot tell the sign of the bivector square".to_string()),
+        }
+    }
+
+    /// Checks `rest` is null and commutes with `self`, so `self` splits into commuting parts.
+    fn check_null_split(&self, basis: &Basis, rest: &MultiVector) -> Result<(), String> {
+        if rest.multiply(basis, rest)?.0.is_empty() && self.commutator(basis, rest)?.0.is_empty() {
+            Ok(())
+        } else {
+            Err("Only simple bivectors or those with a null commuting square part".to_string())
+        }
+    }
+
+    /// Replaces named symbols in every coefficient, see `Symbols::substitute`.
+    pub fn substitute(&self, map: &BTreeMap<Symbol, Symbols>) -> Result<MultiVector, String> {
         let mut result = MultiVector::default();
+        for (elem, sym) in self.0.iter() {
+            let term = MultiVector(
+                vec![(elem.clone(), sym.substitute(map)?)]
+                    .into_iter()
+                    .collect(),
+            );
+            result = result + term;
+        }
+        Ok(result)
+    }
 
-        for (lhs_elem, lhs_sym) in self.0.iter() {
-            for (rhs_elem, rhs_sym) in rhs.0.iter() {
-                let sym = lhs_sym * rhs_sym;
-                match lhs_elem.multiply(basis, rhs_elem)?.elems_and_sign() {
-                    (SquaredElement::Zero, _) => {}
-                    (SquaredElement::One, es) => {
-                        let rhs = MultiVector(vec![(es, sym)].into_iter().collect());
-                        result = result + rhs;
+    /// The partial derivative of every coefficient, see `Symbols::derivative`.
+    pub fn derivative(&self, var: &Symbol) -> Result<MultiVector, String> {
+        let mut result = MultiVector::default();
+        for (elem, sym) in self.0.iter() {
+            let term = MultiVector(
+                vec![(elem.clone(), sym.derivative(var)?)]
+                    .into_iter()
+                    .collect(),
+            );
+            result = result + term;
+        }
+        Ok(result)
+    }
+
+    /// Every coefficient reduced by the relations, see `Symbols::reduce`.
+    pub fn reduce(&self, relations: &Relations) -> MultiVector {
+        MultiVector(
+            self.0
+                .iter()
+                .map(|(elem, sym)| (elem.clone(), sym.reduce(relations)))
+                .filter(|(_elem, sym)| !sym.is_zero())
+                .collect(),
+        )
+    }
+
+    /// The components of `x ~x - 1`, which are zero when `x` is normalized.
+    pub fn unit_relations(&self, basis: &Basis) -> Result<Vec<Symbols>, String> {
+        let norm = self.multiply(basis, &self.reverse())? + scalar_mv(one().invert());
+        Ok(norm.0.into_values().collect())
+    }
+
+    /// The components of `x x`, which are zero when `x` is null.
+    pub fn null_relations(&self, basis: &Basis) -> Result<Vec<Symbols>, String> {
+        Ok(self.multiply(basis, self)?.0.into_values().collect())
+    }
+
+    /// Pairs each symbol of a multivector like `a_e1 e1 + a_e2 e2` with the matching component
+    /// of `values`, for use with `substitute`. Missing components are zero.
+    pub fn bindings(&self, values: &MultiVector) -> Result<BTreeMap<Symbol, Symbols>, String> {
+        let mut map = BTreeMap::new();
+        for (elem, sym) in self.0.iter() {
+            let single = match sym.numer.0.iter().next() {
+                Some((powers, scale)) if sym.is_polynomial() && sym.numer.0.len() == 1 => {
+                    match powers.iter().next() {
+                        Some((sym, 1)) if powers.len() == 1 && scale == &lift_integer(1) => {
+                            Some(sym.clone())
+                        }
+                        _ => None,
                     }
-                    (SquaredElement::MinusOne, es) => {
-                        let rhs = MultiVector(vec![(es, sym.invert())].into_iter().collect());
-                        result = result + rhs;
+                }
+                _ => None,
+            };
+            let single = single.ok_or_else(|| {
+                format!(
+                    "Component {:?} is not a single symbol, given {:?}",
+                    elem, sym
+                )
+            })?;
+            let value = values.0.get(elem).cloned().unwrap_or_else(Symbols::zero);
+            map.insert(single, value);
+        }
+        Ok(map)
+    }
+
+    /// The floating point value of every element of `basis`, including zeros.
+    pub fn evaluate(
+        &self,
+        basis: &Basis,
+        map: &BTreeMap<Symbol, f64>,
+    ) -> Result<BTreeMap<Element, f64>, String> {
+        let mut values: BTreeMap<Element, f64> = basis
+            .elements()
+            .into_iter()
+            .map(|elem| (elem, 0.0))
+            .collect();
+        for (elem, sym) in self.0.iter() {
+            let value = values
+                .get_mut(elem)
+                .ok_or_else(|| format!("Element {:?} is not in the basis", elem))?;
+            *value = sym.evaluate(map)?;
+        }
+        Ok(values)
+    }
+
+    /// The scalar coefficient if there are no other elements.
+    pub fn scalar_part(&self) -> Option<Symbols> {
+        let scalar = Element(BTreeSet::new());
+        if self.0.keys().all(|elem| elem == &scalar) {
+            Some(self.0.get(&scalar).cloned().unwrap_or_else(Symbols::zero))
+        } else {
+            None
+        }
+    }
+
+    fn negate_grades(&self, negate: impl Fn(Grade) -> bool) -> MultiVector {
+        MultiVector(
+            self.0
+                .iter()
+                .map(|(elem, sym)| {
+                    if negate(elem.grade()) {
+                        (elem.clone(), sym.invert())
+                    } else {
+                        (elem.clone(), sym.clone())
                     }
+                })
+                .collect(),
+        )
+    }
+
+    /// Extends a product on elements bilinearly over both multivectors.
+    fn product(
+        &self,
+        basis: &Basis,
+        rhs: &MultiVector,
+        element_product: impl Fn(&Element, &Basis, &Element) -> Result<SimplifiedElement, String>,
+    ) -> Result<MultiVector, String> {
+        let mut result = MultiVector::default();
+
+        for (lhs_elem, lhs_sym) in self.0.iter() {
+            for (rhs_elem, rhs_sym) in rhs.0.iter() {
+                let elem = element_product(lhs_elem, basis, rhs_elem)?;
+                if elem != SimplifiedElement::Zero {
+                    result = result + signed_term(elem, lhs_sym * rhs_sym);
                 }
             }
         }
@@ -32,6 +497,28 @@ impl MultiVector {
         Ok(result)
     }
 
+    /// Extends a map on elements linearly over the multivector.
+    fn map_elements(
+        &self,
+        basis: &Basis,
+        element_map: impl Fn(&Element, &Basis) -> Result<SimplifiedElement, String>,
+    ) -> Result<MultiVector, String> {
+        let mut result = MultiVector::default();
+
+        for (elem, sym) in self.0.iter() {
+            result = result + signed_term(element_map(elem, basis)?, sym.clone());
+        }
+
+        Ok(result)
+    }
+
+    /// The components ordered by grade, then by element.
+    pub fn by_grade(&self) -> Vec<(&Element, &Symbols)> {
+        let mut components: Vec<_> = self.0.iter().collect();
+        components.sort_by_key(|(elem, _sym)| (elem.grade(), *elem));
+        components
+    }
+
     pub fn project(&self, basis: &Basis, grades: &BTreeSet<Grade>) -> MultiVector {
         let elements: BTreeSet<Element> = grades.iter().flat_map(|g| basis.grade(*g)).collect();
         MultiVector(
@@ -44,9 +531,122 @@ impl MultiVector {
     }
 }
 
-impl std::default::Default for MultiVector {
-    fn default() -> MultiVector {
-        MultiVector(BTreeMap::new())
+fn one() -> Symbols {
+    Symbols::constant(lift_integer(1))
+}
+
+fn scalar_mv(sym: Symbols) -> MultiVector {
+    MultiVector::default() + signed_term(SimplifiedElement::Positive(Element(BTreeSet::new())), sym)
+}
+
+fn scalar_component(mv: &MultiVector) -> Symbols {
+    mv.0.get(&Element(BTreeSet::new()))
+        .cloned()
+        .unwrap_or_else(Symbols::zero)
+}
+
+/// The exponential of a bivector squaring to the scalar `square`.
+fn exp_simple(bivector: &MultiVector, square: &Symbols) -> Result<MultiVector, String> {
+    let (even, odd, magnitude) = match square.definite_sign() {
+        Some(Ordering::Equal) => return Ok(scalar_mv(one()) + bivector.clone()),
+        Some(Ordering::Less) => (Function::Cos, Function::Sin, square.invert()),
+        Some(Ordering::Greater) => (Function::Cosh, Function::Sinh, square.clone()),
+        None => return Err("Cannot tell the sign of the bivector square".to_string()),
+    };
+    let u = Symbols::apply(Function::Sqrt, vec![magnitude.clone()]);
+    let odd = &Symbols::apply(odd, vec![u.clone()]) * &u.recip()?;
+    let odd = Symbols::apply(Function::IfZero, vec![magnitude, one(), odd]);
+
+    Ok(scalar_mv(Symbols::apply(even, vec![u])) + bivector.scale_symbols(&odd))
+}
+
+/// Picks `zero` component-wise where `x` is zero and `other` elsewhere, for closed forms that
+/// divide by `x`.
+fn if_zero(x: &Symbols, zero: &MultiVector, other: &MultiVector) -> MultiVector {
+    let elems: BTreeSet<&Element> = zero.0.keys().chain(other.0.keys()).collect();
+    let component =
+        |mv: &MultiVector, elem: &Element| mv.0.get(elem).cloned().unwrap_or_else(Symbols::zero);
+    let terms = elems.into_iter().filter_map(|elem| {
+        let args = vec![x.clone(), component(zero, elem), component(other, elem)];
+        let sym = Symbols::apply(Function::IfZero, args);
+        if sym.is_zero() {
+            None
+        } else {
+            Some((elem.clone(), sym))
+        }
+    });
+    MultiVector(terms.collect())
+}
+
+fn half() -> BigRational {
+    BigRational::new(1.into(), 2.into())
+}
+
+fn no_inverse() -> String {
+    "Multivector has no inverse, its norm is zero".to_string()
+}
+
+fn signed_term(elem: SimplifiedElement, sym: Symbols) -> MultiVector {
+    match elem {
+        SimplifiedElement::Zero => MultiVector::default(),
+        SimplifiedElement::Positive(es) => MultiVector(vec![(es, sym)].into_iter().collect()),
+        SimplifiedElement::Negative(es) => {
+            MultiVector(vec![(es, sym.invert())].into_iter().collect())
+        }
+        SimplifiedElement::Sum(terms) => terms
+            .into_iter()
+            .map(|(es, coef)| MultiVector(std::iter::once((es, &coef * &sym)).collect()))
+            .fold(MultiVector::default(), |prev, term| prev + term),
+    }
+}
+
+/// Components by grade like `(a)e1 + (b - c)e12`, the scalar part is just `(a)`.
+impl std::fmt::Display for MultiVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write_terms(f, |elem| elem.to_string())
+    }
+}
+
+/// A multivector written with the blade names of a basis, see `MultiVector::display`.
+pub struct MultiVectorDisplay<'a> {
+    mv: &'a MultiVector,
+    basis: &'a Basis,
+}
+
+impl MultiVector {
+    /// Writes coefficients of the basis blades, which `parse::parse_multivector` reads back
+    /// in the same basis.
+    pub fn display<'a>(&'a self, basis: &'a Basis) -> MultiVectorDisplay<'a> {
+        MultiVectorDisplay { mv: self, basis }
+    }
+
+    fn write_terms(
+        &self,
+        f: &mut std::fmt::Formatter,
+        name: impl Fn(&Element) -> String,
+    ) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        for (idx, (elem, sym)) in self.by_grade().into_iter().enumerate() {
+            if idx > 0 {
+                write!(f, " + ")?;
+            }
+            if elem.0.is_empty() {
+                write!(f, "({})", sym)?;
+            } else {
+                write!(f, "({}){}", sym, name(elem))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for MultiVectorDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.mv
+            .orient(self.basis)
+            .write_terms(f, |elem| elem.display(self.basis).to_string())
     }
 }
 
@@ -54,20 +654,17 @@ impl std::ops::Add for MultiVector {
     type Output = MultiVector;
 
     fn add(self, rhs: MultiVector) -> MultiVector {
-        self.0.into_iter().chain(rhs.0.into_iter()).fold(
-            MultiVector::default(),
-            |mut prev, (elem, sym)| {
-                let existing = prev
-                    .0
-                    .remove(&elem)
-                    .unwrap_or_else(|| Symbols(BTreeMap::new()));
+        self.0
+            .into_iter()
+            .chain(rhs.0)
+            .fold(MultiVector::default(), |mut prev, (elem, sym)| {
+                let existing = prev.0.remove(&elem).unwrap_or_else(Symbols::zero);
                 let sum = existing + sym;
-                if !sum.0.is_empty() {
+                if !sum.is_zero() {
                     prev.0.insert(elem, sum);
                 }
                 prev
-            },
-        )
+            })
     }
 }
 
@@ -77,90 +674,637 @@ mod tests {
     use std::collections::BTreeSet;
 
     use crate::basis::Vector;
+    use crate::polynomial::{Polynomial, Symbol};
     use crate::symbols::lift_integer;
 
     use super::*;
 
-    const G2: Basis = Basis {
-        zero: 0,
-        positive: 2,
-        negative: 0,
-    };
+    const G2: Basis = Basis::new(0, 2, 0);
 
     #[test]
-    fn test_simple_mult() {
+    fn test_simple_mult() -> Result<(), String> {
         // (5 e2) (3a e2 + bb e1) = 15a - 5 bb e12
-        // yeah the length of these expressions is absolutely bananas...
-        let lhs = MultiVector(
-            vec![(
-                Element(vec![Vector(1)].into_iter().collect()),
-                Symbols(
-                    vec![(BTreeMap::new(), lift_integer(5))]
-                        .into_iter()
-                        .collect(),
-                ),
-            )]
-            .into_iter()
-            .collect(),
-        );
-        let rhs = MultiVector(
-            vec![
-                (
-                    Element(vec![Vector(1)].into_iter().collect()),
-                    Symbols(
-                        vec![(
-                            vec![("a".to_string(), 1)].into_iter().collect(),
-                            lift_integer(3),
-                        )]
-                        .into_iter()
-                        .collect(),
-                    ),
-                ),
-                (
-                    Element(vec![Vector(0)].into_iter().collect()),
-                    Symbols(
-                        vec![(
-                            vec![("b".to_string(), 2)].into_iter().collect(),
-                            lift_integer(1),
-                        )]
-                        .into_iter()
-                        .collect(),
-                    ),
-                ),
-            ]
-            .into_iter()
-            .collect(),
-        );
+        let lhs = MultiVector::parse(&G2, "5*e1")?;
+        let rhs = MultiVector::parse(&G2, "3*a*e1 + b^2*e0")?;
 
         let expected = MultiVector(
             vec![
                 (
                     Element(BTreeSet::new()),
-                    Symbols(
-                        vec![(
-                            vec![("a".to_string(), 1)].into_iter().collect(),
-                            lift_integer(15),
-                        )]
-                        .into_iter()
-                        .collect(),
-                    ),
+                    Symbols::symbol("a").scale(&lift_integer(15)),
                 ),
                 (
                     Element(vec![Vector(0), Vector(1)].into_iter().collect()),
-                    Symbols(
-                        vec![(
-                            vec![("b".to_string(), 2)].into_iter().collect(),
-                            lift_integer(-5),
-                        )]
-                        .into_iter()
-                        .collect(),
-                    ),
+                    (&Symbols::symbol("b") * &Symbols::symbol("b")).scale(&lift_integer(-5)),
                 ),
             ]
             .into_iter()
             .collect(),
         );
 
-        assert_eq!(lhs.multiply(&G2, &rhs).unwrap(), expected);
+        assert_eq!(lhs.multiply(&G2, &rhs)?, expected);
+        assert_eq!(expected.to_string(), "(15*a) + (-5*b^2)e01");
+        Ok(())
+    }
+
+    fn term(vs: &[usize], syms: &[&str], scale: isize) -> MultiVector {
+        let mut powers = BTreeMap::new();
+        for sym in syms {
+            *powers.entry(Symbol::from(*sym)).or_insert(0) += 1;
+        }
+        MultiVector(
+            vec![(
+                Element(vs.iter().cloned().map(Vector).collect()),
+                Symbols::from(Polynomial(
+                    vec![(powers, lift_integer(scale))].into_iter().collect(),
+                )),
+            )]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_outer_mixed_grades() {
+        // (1 + a e1) ^ (b e1 + c e2) = b e1 + c e2 + ac e12
+        let lhs = term(&[], &[], 1) + term(&[0], &["a"], 1);
+        let rhs = term(&[0], &["b"], 1) + term(&[1], &["c"], 1);
+
+        let expected =
+            term(&[0], &["b"], 1) + term(&[1], &["c"], 1) + term(&[0, 1], &["a", "c"], 1);
+
+        assert_eq!(lhs.outer(&G2, &rhs).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_contract_vector_onto_bivector_g3() {
+        // (a e1 + b e2) _| (c e12) = ac e2 - bc e1
+        let g3 = Basis::new(0, 3, 0);
+        let lhs = term(&[0], &["a"], 1) + term(&[1], &["b"], 1);
+        let rhs = term(&[0, 1], &["c"], 1);
+
+        assert_eq!(
+            lhs.left_contraction(&g3, &rhs).unwrap(),
+            term(&[1], &["a", "c"], 1) + term(&[0], &["b", "c"], -1)
+        );
+        assert_eq!(
+            rhs.right_contraction(&g3, &lhs).unwrap(),
+            term(&[1], &["a", "c"], -1) + term(&[0], &["b", "c"], 1)
+        );
+        assert_eq!(
+            lhs.scalar_product(&g3, &lhs).unwrap(),
+            term(&[], &["a", "a"], 1) + term(&[], &["b", "b"], 1)
+        );
+    }
+
+    #[test]
+    fn test_inner_products_pga3() {
+        // Plane x = 0 (e1) and line through the origin along z (e12)
+        let pga3 = Basis::new(1, 3, 0);
+        let plane = term(&[1], &[], 1) + term(&[0], &["d"], 1);
+        let line = term(&[1, 2], &[], 1);
+
+        // The plane's distance from the origin does not affect the projection
+        assert_eq!(
+            plane.left_contraction(&pga3, &line).unwrap(),
+            term(&[2], &[], 1)
+        );
+        assert_eq!(line.fat_dot(&pga3, &plane).unwrap(), term(&[2], &[], -1));
+        assert_eq!(
+            line.hestenes_inner(&pga3, &line).unwrap(),
+            term(&[], &[], -1)
+        );
+        assert_eq!(
+            term(&[], &["s"], 1).hestenes_inner(&pga3, &line).unwrap(),
+            MultiVector::default()
+        );
+        assert_eq!(
+            term(&[], &["s"], 1).fat_dot(&pga3, &line).unwrap(),
+            term(&[1, 2], &["s"], 1)
+        );
+    }
+
+    #[test]
+    fn test_regressive_joins_pga3_points() {
+        let pga3 = Basis::new(1, 3, 0);
+        // Points are trivectors, the origin is e123 and (x, 0, 0) adds x e032 = -x e023
+        let origin = term(&[1, 2, 3], &[], 1);
+        let on_x_axis = term(&[1, 2, 3], &[], 1) + term(&[0, 2, 3], &["x"], -1);
+        let elsewhere = term(&[1, 2, 3], &[], 1) + term(&[0, 1, 2], &["z"], 1);
+
+        // The line through them is the x axis, e23
+        assert_eq!(
+            origin.regressive(&pga3, &on_x_axis).unwrap(),
+            term(&[2, 3], &["x"], 1)
+        );
+
+        // Both points lie on the line joining them, so joining again is degenerate
+        let line = on_x_axis.regressive(&pga3, &elsewhere).unwrap();
+        assert_eq!(
+            on_x_axis.regressive(&pga3, &line).unwrap(),
+            MultiVector::default()
+        );
+        assert_eq!(
+            elsewhere.regressive(&pga3, &line).unwrap(),
+            MultiVector::default()
+        );
+
+        assert_eq!(
+            origin.join(&pga3, &on_x_axis).unwrap(),
+            term(&[2, 3], &["x"], 1)
+        );
+
+        // Planes x = 0 and y = 1 meet in a line parallel to the z axis, e12 - e02
+        let x_plane = term(&[1], &[], 1);
+        let y_plane = term(&[2], &[], 1) + term(&[0], &[], -1);
+        assert_eq!(
+            x_plane.meet(&pga3, &y_plane).unwrap(),
+            term(&[1, 2], &[], 1) + term(&[0, 1], &[], 1)
+        );
+    }
+
+    #[test]
+    fn test_duals_of_pga3_plane() {
+        let pga3 = Basis::new(1, 3, 0);
+        let plane = term(&[0], &["d"], 1) + term(&[1], &["a"], 1);
+
+        let dual = plane.poincare_dual(&pga3).unwrap();
+        assert_eq!(
+            dual,
+            term(&[1, 2, 3], &["d"], 1) + term(&[0, 2, 3], &["a"], -1)
+        );
+        assert_eq!(dual.left_complement(&pga3).unwrap(), plane);
+
+        // The Hodge dual loses the ideal part
+        assert_eq!(
+            plane.hodge_dual(&pga3).unwrap(),
+            term(&[0, 2, 3], &["a"], -1)
+        );
+    }
+
+    #[test]
+    fn test_involutions_by_grade() {
+        let g3 = Basis::new(0, 3, 0);
+        let mv = g3
+            .elements()
+            .into_iter()
+            .fold(MultiVector::default(), |mv, elem| {
+                let vs: Vec<usize> = elem.0.iter().map(|v| v.0).collect();
+                mv + term(&vs, &["x"], 1)
+            });
+        let signs = |mv: MultiVector| -> Vec<isize> {
+            g3.elements()
+                .iter()
+                .map(|elem| {
+                    let scale = &mv.0[elem].numer.0[&vec![("x".into(), 1)].into_iter().collect()];
+                    if scale == &lift_integer(1) {
+                        1
+                    } else {
+                        -1
+                    }
+                })
+                .collect()
+        };
+
+        // Elements are ordered 1, e1, e2, e3, e12, e13, e23, e123
+        assert_eq!(signs(mv.reverse()), vec![1, 1, 1, 1, -1, -1, -1, -1]);
+        assert_eq!(
+            signs(mv.grade_involution()),
+            vec![1, -1, -1, -1, 1, 1, 1, -1]
+        );
+        assert_eq!(
+            signs(mv.clifford_conjugate()),
+            vec![1, -1, -1, -1, -1, -1, -1, 1]
+        );
+        assert_eq!(mv.reverse().reverse(), mv);
+    }
+
+    #[test]
+    fn test_reverse_of_product() {
+        // ~(ab) = ~b ~a
+        let g3 = Basis::new(0, 3, 0);
+        let a = term(&[], &["s"], 1) + term(&[0], &["a"], 1) + term(&[1, 2], &["b"], 1);
+        let b = term(&[0, 1], &["c"], 1) + term(&[2], &["d"], 1) + term(&[0, 1, 2], &["e"], 1);
+
+        assert_eq!(
+            a.multiply(&g3, &b).unwrap().reverse(),
+            b.reverse().multiply(&g3, &a.reverse()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commutators_of_g3() {
+        let g3 = Basis::new(0, 3, 0);
+        let e12 = term(&[0, 1], &[], 1);
+        let e23 = term(&[1, 2], &[], 1);
+
+        // Bivectors close under the commutator, like angular velocities
+        assert_eq!(e12.commutator(&g3, &e23).unwrap(), term(&[0, 2], &[], 1));
+        assert_eq!(
+            e12.anti_commutator(&g3, &e23).unwrap(),
+            MultiVector::default()
+        );
+
+        // For vectors they split the product into the wedge and dot parts
+        let a = term(&[0], &["a"], 1) + term(&[1], &["b"], 1);
+        let b = term(&[0], &["c"], 1) + term(&[2], &["d"], 1);
+        assert_eq!(a.commutator(&g3, &b).unwrap(), a.outer(&g3, &b).unwrap());
+        assert_eq!(
+            a.anti_commutator(&g3, &b).unwrap(),
+            a.scalar_product(&g3, &b).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commutator_halves_exactly() {
+        let g3 = Basis::new(0, 3, 0);
+        let third = BigRational::new(1.into(), 3.into());
+        let a = term(&[0], &["a"], 1).scale(&third);
+        let b = term(&[0], &[], 1) + term(&[1], &[], 1);
+
+        // (a/3 e1) x (e1 + e2) = a/3 e12, anti-commutator gives a/3
+        assert_eq!(
+            a.commutator(&g3, &b).unwrap(),
+            term(&[0, 1], &["a"], 1).scale(&third)
+        );
+        assert_eq!(
+            a.anti_commutator(&g3, &b).unwrap(),
+            term(&[], &["a"], 1).scale(&third)
+        );
+    }
+
+    #[test]
+    fn test_sandwich_keeps_grades() {
+        // Conformal model, where an unnormalised even element can produce a quintvector
+        let cga3 = Basis::new(0, 4, 1);
+        let versor = term(&[], &["s"], 1)
+            + term(&[0, 1], &["a"], 1)
+            + term(&[2, 4], &["b"], 1)
+            + term(&[0, 1, 2, 3], &["p"], 1);
+        let x = term(&[0], &["x"], 1) + term(&[3], &["y"], 1) + term(&[4], &["z"], 1);
+
+        let full = versor
+            .multiply(&cga3, &x)
+            .unwrap()
+            .multiply(&cga3, &versor.reverse())
+            .unwrap();
+        let sandwich = versor.sandwich(&cga3, &x).unwrap();
+
+        assert_eq!(full.grades(), vec![1, 5].into_iter().collect());
+        assert_eq!(sandwich.grades(), x.grades());
+        assert_eq!(sandwich, full.project(&cga3, &x.grades()));
+        assert_eq!(versor.sandwich_to(&cga3, &x, &full.grades()).unwrap(), full);
+    }
+
+    #[test]
+    fn test_sandwich_reflects_in_odd_versor() {
+        let g3 = Basis::new(0, 3, 0);
+        let x = term(&[0], &["a"], 1) + term(&[1], &["b"], 1) + term(&[1, 2], &["c"], 1);
+
+        // A unit vector e1 reflects in the plane orthogonal to it, which holds e2 ^ e3
+        let reflected = term(&[0], &["a"], -1) + term(&[1], &["b"], 1) + term(&[1, 2], &["c"], 1);
+        assert_eq!(term(&[0], &[], 1).sandwich(&g3, &x).unwrap(), reflected);
+        // Two reflections are a rotation, e1 e2 rotates by a half turn
+        let rotated = term(&[0], &["a"], -1) + term(&[1], &["b"], -1) + term(&[1, 2], &["c"], -1);
+        assert_eq!(term(&[0, 1], &[], 1).sandwich(&g3, &x).unwrap(), rotated);
+    }
+
+    fn assert_inverse(basis: &Basis, mv: &MultiVector) {
+        let inverse = mv.inverse(basis).unwrap();
+        let one = term(&[], &[], 1);
+
+        assert_eq!(mv.multiply(basis, &inverse).unwrap(), one);
+        assert_eq!(inverse.multiply(basis, mv).unwrap(), one);
+    }
+
+    #[test]
+    fn test_inverse_of_vector() {
+        let g3 = Basis::new(0, 3, 0);
+        let v = term(&[0], &["a"], 1) + term(&[1], &["b"], 1);
+
+        // (a e1 + b e2)^-1 = (a e1 + b e2) / (aa + bb)
+        let norm = (term(&[], &["a", "a"], 1) + term(&[], &["b", "b"], 1))
+            .scalar_part()
+            .unwrap();
+        assert_eq!(
+            v.inverse(&g3).unwrap(),
+            v.scale_symbols(&norm.recip().unwrap())
+        );
+        assert_eq!(
+            v.inverse(&g3).unwrap().0[&Element(vec![Vector(0)].into_iter().collect())].denom,
+            norm.numer
+        );
+    }
+
+    #[test]
+    fn test_inverse_of_general_multivectors() {
+        let g3 = Basis::new(0, 3, 0);
+        assert_inverse(
+            &g3,
+            &(term(&[], &["s"], 1) + term(&[0], &["a"], 1) + term(&[1, 2], &["b"], 1)),
+        );
+
+        let sta = Basis::new(0, 1, 3);
+        assert_inverse(
+            &sta,
+            &(term(&[], &["s"], 1)
+                + term(&[0], &["a"], 1)
+                + term(&[1, 2, 3], &["b"], 1)
+                + term(&[0, 1, 2, 3], &["c"], 1)),
+        );
+
+        let cga3 = Basis::new(0, 4, 1);
+        assert_inverse(
+            &cga3,
+            &(term(&[], &["s"], 1) + term(&[0], &["a"], 1) + term(&[3, 4], &["b"], 1)),
+        );
+    }
+
+    #[test]
+    fn test_null_vectors_have_no_inverse() {
+        let pga3 = Basis::new(1, 3, 0);
+        assert!(term(&[0], &["a"], 1).inverse(&pga3).is_err());
+
+        let g3 = Basis::new(0, 3, 0);
+        assert!((term(&[], &[], 1) + term(&[0], &[], 1))
+            .inverse(&g3)
+            .is_err());
+    }
+
+    #[test]
+    fn test_integer_powers() -> Result<(), String> {
+        let g3 = Basis::new(0, 3, 0);
+        let x = MultiVector::parse(&g3, "s + a*e0 + b*e12")?;
+
+        let mut repeated = MultiVector::parse(&g3, "1")?;
+        for n in 0..=5 {
+            assert_eq!(x.pow(&g3, n)?, repeated);
+            repeated = repeated.multiply(&g3, &x)?;
+        }
+        let v = MultiVector::parse(&g3, "a*e0 + b*e1")?;
+        assert_eq!(
+            v.pow(&g3, -3)?,
+            MultiVector::parse(&g3, "(a*e0 + b*e1)/(a^2 + b^2)^2")?
+        );
+
+        let pga3 = Basis::new(1, 3, 0);
+        let null = MultiVector::parse(&pga3, "a*e0")?;
+        assert_eq!(null.pow(&pga3, 2)?, MultiVector::default());
+        assert!(null.pow(&pga3, -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_basis_conformal_points() -> Result<(), String> {
+        // e0 = origin and e1 = infinity are null with e0 . e1 = -1, e2 is Euclidean
+        let int = |x: isize| Symbols::constant(lift_integer(x));
+        let cga1 = Basis::from_metric(crate::basis::Metric::new(vec![
+            vec![int(0), int(-1), int(0)],
+            vec![int(-1), int(0), int(0)],
+            vec![int(0), int(0), int(1)],
+        ])?);
+        let up = |x: &str| MultiVector::parse(&cga1, &format!("e0 + {x}*e2 + {x}^2/2*e1", x = x));
+        let (x, y) = (up("x")?, up("y")?);
+
+        assert_eq!(x.multiply(&cga1, &x)?, MultiVector::default());
+        assert_eq!(
+            x.left_contraction(&cga1, &y)?,
+            MultiVector::parse(&cga1, "-1/2*(x - y)^2")?
+        );
+        // Associative even though element products are sums
+        let z = MultiVector::parse(&cga1, "a + b*e01 + c*e2")?;
+        assert_eq!(
+            x.multiply(&cga1, &y)?.multiply(&cga1, &z)?,
+            x.multiply(&cga1, &y.multiply(&cga1, &z)?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_outer_degenerate_basis() {
+        // In PGA2 e0 squares to zero but (e0 + e1) ^ (e0 + e2) = e02 - e01 + e12
+        let pga2 = Basis::new(1, 2, 0);
+        let lhs = term(&[0], &[], 1) + term(&[1], &[], 1);
+        let rhs = term(&[0], &[], 1) + term(&[2], &[], 1);
+
+        let expected = term(&[0, 2], &[], 1) + term(&[0, 1], &[], -1) + term(&[1, 2], &[], 1);
+
+        assert_eq!(lhs.outer(&pga2, &rhs).unwrap(), expected);
+        assert_eq!(lhs.outer(&pga2, &lhs).unwrap(), MultiVector::default());
+        assert_eq!(lhs.multiply(&pga2, &lhs).unwrap(), term(&[], &[], 1));
+    }
+
+    #[test]
+    fn test_exp_of_simple_bivectors() {
+        // exp(a e01) = 1 + a e01 in PGA3
+        let pga3 = Basis::new(1, 3, 0);
+        let translation = term(&[0, 1], &["a"], 1);
+        assert_eq!(
+            translation.exp(&pga3).unwrap(),
+            term(&[], &[], 1) + translation.clone()
+        );
+        assert_eq!(
+            (term(&[], &[], 1) + translation.clone())
+                .log(&pga3)
+                .unwrap(),
+            translation
+        );
+
+        // exp(a e12) = cos(sqrt(aa)) + sin(sqrt(aa)) / sqrt(aa) a e12
+        let sqrt = Symbols::apply(
+            Function::Sqrt,
+            vec![term(&[], &["a", "a"], 1).scalar_part().unwrap()],
+        );
+        let rotation = term(&[0, 1], &["a"], 1);
+        let sinc = &Symbols::apply(Function::Sin, vec![sqrt.clone()]) * &sqrt.recip().unwrap();
+        let guarded = Symbols::apply(
+            Function::IfZero,
+            vec![
+                term(&[], &["a", "a"], 1).scalar_part().unwrap(),
+                one(),
+                sinc,
+            ],
+        );
+        let expected = scalar_mv(Symbols::apply(Function::Cos, vec![sqrt.clone()]))
+            + rotation.scale_symbols(&guarded);
+        assert_eq!(rotation.exp(&G2).unwrap(), expected);
+
+        // A boost in STA squares to a positive scalar
+        let sta = Basis::new(0, 1, 3);
+        let boost = term(&[0, 1], &["a"], 1).exp(&sta).unwrap();
+        assert_eq!(
+            boost.0[&Element(BTreeSet::new())],
+            Symbols::apply(Function::Cosh, vec![sqrt])
+        );
+    }
+
+    #[test]
+    fn test_exp_log_need_known_signs() {
+        let sta = Basis::new(0, 1, 3);
+        // Boost and rotation together square to a scalar of unknown sign plus a pseudoscalar
+        let mixed = term(&[0, 1], &["a"], 1) + term(&[2, 3], &["b"], 1);
+        assert!(mixed.exp(&sta).is_err());
+        assert!(term(&[0], &["a"], 1).exp(&sta).is_err());
+        assert!(term(&[0], &["a"], 1).log(&sta).is_err());
+    }
+
+    #[test]
+    fn test_exp_splits_general_pga3_bivector() {
+        let pga3 = Basis::new(1, 3, 0);
+        // A screw around and along the z axis, the parts commute so exp(B) = exp(Br) exp(Bi)
+        let rotation = term(&[1, 2], &["a"], 1);
+        let translation = term(&[0, 3], &["b"], 1);
+        let screw = (rotation.clone() + translation.clone()).exp(&pga3).unwrap();
+        let product = rotation
+            .exp(&pga3)
+            .unwrap()
+            .multiply(&pga3, &translation.exp(&pga3).unwrap())
+            .unwrap();
+
+        for (a, b) in [(0.5, 2.0), (-1.5, 0.25), (0.0, 2.0)].iter() {
+            let map = values(&[("a", *a), ("b", *b)]);
+            let (screw, product) = (screw.evaluate(&pga3, &map), product.evaluate(&pga3, &map));
+            for (elem, value) in screw.unwrap() {
+                assert!((value - product.as_ref().unwrap()[&elem]).abs() < 1e-12);
+            }
+        }
+        assert_eq!(screw.grades(), vec![0, 2, 4].into_iter().collect());
+    }
+
+    #[test]
+    fn test_exp_log_at_zero_angle() -> Result<(), String> {
+        let pga3 = Basis::new(1, 3, 0);
+        let scalar = Element(BTreeSet::new());
+        let bivector = MultiVector::parse(&pga3, "a*e12 + b*e13 + c*e23 + d*e01 + f*e02 + g*e03")?;
+        let exp = bivector.exp(&pga3)?;
+
+        // The identity, and a pure translation through the general split
+        let zero = values(&[
+            ("a", 0.0),
+            ("b", 0.0),
+            ("c", 0.0),
+            ("d", 0.0),
+            ("f", 0.0),
+            ("g", 0.0),
+        ]);
+        let identity = exp.evaluate(&pga3, &zero)?;
+        assert!(identity
+            .iter()
+            .all(|(elem, value)| { *value == if elem == &scalar { 1.0 } else { 0.0 } }));
+        let translation = values(&[
+            ("a", 0.0),
+            ("b", 0.0),
+            ("c", 0.0),
+            ("d", 2.0),
+            ("f", 0.0),
+            ("g", 0.0),
+        ]);
+        let translator = exp.evaluate(&pga3, &translation)?;
+        assert_eq!(translator[&scalar], 1.0);
+        assert_eq!(
+            translator[&Element(vec![Vector(0), Vector(1)].into_iter().collect())],
+            2.0
+        );
+
+        // log of the identity rotor and of a pure translator
+        let motor = MultiVector::parse(&pga3, "s + a*e12 + d*e03 + p*e0123")?;
+        let log = motor.log(&pga3)?;
+        let identity = log.evaluate(
+            &pga3,
+            &values(&[("s", 1.0), ("a", 0.0), ("d", 0.0), ("p", 0.0)]),
+        )?;
+        assert!(identity.values().all(|value| *value == 0.0));
+        let translator = log.evaluate(
+            &pga3,
+            &values(&[("s", 1.0), ("a", 0.0), ("d", 2.0), ("p", 0.0)]),
+        )?;
+        assert_eq!(
+            translator[&Element(vec![Vector(0), Vector(3)].into_iter().collect())],
+            2.0
+        );
+
+        let sta = Basis::new(0, 1, 3);
+        let boost = MultiVector::parse(&sta, "s + a*e01")?.log(&sta)?;
+        let identity = boost.evaluate(&sta, &values(&[("s", 1.0), ("a", 0.0)]))?;
+        assert!(identity.values().all(|value| *value == 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_inverse() -> Result<(), String> {
+        let g3 = Basis::new(0, 3, 0);
+        let v = term(&[0], &["a"], 1) + term(&[1], &["b"], 1);
+        let inverse = v.inverse(&g3)?;
+
+        // (3 e1 + 4 e2)^-1 = 3/25 e1 + 4/25 e2
+        let values = term(&[0], &[], 3) + term(&[1], &[], 4);
+        let exact = inverse.substitute(&v.bindings(&values)?)?;
+        assert_eq!(
+            exact,
+            (term(&[0], &[], 3) + term(&[1], &[], 4)).scale(&BigRational::new(1.into(), 25.into()))
+        );
+
+        let map = vec![(Symbol::from("a"), 3.0), (Symbol::from("b"), 4.0)]
+            .into_iter()
+            .collect();
+        let numeric = inverse.evaluate(&g3, &map)?;
+        assert_eq!(numeric.len(), 8);
+        assert!((numeric[&Element(vec![Vector(0)].into_iter().collect())] - 0.12).abs() < 1e-12);
+        assert!((numeric[&Element(vec![Vector(1)].into_iter().collect())] - 0.16).abs() < 1e-12);
+        assert_eq!(numeric[&Element(BTreeSet::new())], 0.0);
+
+        assert!(inverse.bindings(&values).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_derivative_of_square() -> Result<(), String> {
+        // d/da (a e1 + b e12)^2 = d/da (aa - bb) = 2a
+        let g3 = Basis::new(0, 3, 0);
+        let v = term(&[0], &["a"], 1) + term(&[0, 1], &["b"], 1);
+        let square = v.multiply(&g3, &v)?;
+
+        assert_eq!(square.derivative(&Symbol::from("a"))?, term(&[], &["a"], 2));
+        assert_eq!(v.derivative(&Symbol::from("b"))?, term(&[0, 1], &[], 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reduce_unit_rotor() -> Result<(), String> {
+        let g3 = Basis::new(0, 3, 0);
+        let r = term(&[], &["a"], 1) + term(&[0, 1], &["b"], 1);
+        let relations = Relations::new(&r.unit_relations(&g3)?)?;
+
+        assert_eq!(
+            r.multiply(&g3, &r.reverse())?.reduce(&relations),
+            term(&[], &[], 1)
+        );
+
+        // (aa - bb) x e1 becomes (1 - 2bb) x e1
+        let x = term(&[0], &["x"], 1);
+        let rotated = r.sandwich(&g3, &x)?.reduce(&relations);
+        assert_eq!(
+            rotated.0[&Element(vec![Vector(0)].into_iter().collect())],
+            (term(&[0], &["x"], 1) + term(&[0], &["b", "b", "x"], -2)).0
+                [&Element(vec![Vector(0)].into_iter().collect())]
+        );
+
+        let n = term(&[0], &["a"], 1) + term(&[1], &["a"], 1);
+        assert!(Relations::new(&n.null_relations(&g3)?).is_ok());
+        Ok(())
+    }
+
+    fn values(pairs: &[(&str, f64)]) -> BTreeMap<Symbol, f64> {
+        pairs
+            .iter()
+            .map(|(name, value)| (Symbol::from(*name), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_display() {
+        let mv = term(&[0, 1], &["b"], 1) + term(&[1], &["a"], 3) + term(&[], &["c"], -1);
+        assert_eq!(mv.to_string(), "(-c) + (3*a)e1 + (b)e01");
+        assert_eq!(MultiVector::default().to_string(), "0");
     }
 }
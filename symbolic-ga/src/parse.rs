@@ -0,0 +1,441 @@
+use std::collections::BTreeSet;
+use std::iter::Peekable;
+use std::str::FromStr;
+use std::vec::IntoIter;
+
+use num::bigint::BigInt;
+use num::rational::BigRational;
+use num::ToPrimitive;
+
+use crate::basis::{Basis, Vector};
+use crate::element::Element;
+use crate::function::Function;
+use crate::multivector::MultiVector;
+use crate::symbols::{lift_integer, Symbols};
+
+/// Vectors named like `e0e1e2`, each index without leading zeros, or the basis vector names
+/// run together like `xy`, after an `e` for names like `e31`. The order of the vectors is kept
+/// so the name may be any orientation of the element. A blade name gives its blade.
+pub fn try_parse_element(basis: &Basis, name: &str) -> Option<Vec<Vector>> {
+    if let Some((blade, _name)) = basis.blade_names.iter().find(|(_blade, n)| n == name) {
+        return Some(blade.clone());
+    }
+    match &basis.names {
+        Some(names) => parse_named_element(names, name).or_else(|| {
+            let rest = name.strip_prefix('e')?;
+            match rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                true => None,
+                false => parse_named_element(names, rest),
+            }
+        }),
+        None => parse_indexed_element(name),
+    }
+    .filter(|vs| !vs.is_empty())
+}
+
+/// Tries longer names first, backtracking when the rest does not split into names.
+fn parse_named_element(names: &[String], name: &str) -> Option<Vec<Vector>> {
+    if name.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut candidates: Vec<(usize, &String)> = names
+        .iter()
+        .enumerate()
+        .filter(|(_idx, vector)| name.starts_with(vector.as_str()))
+        .collect();
+    candidates.sort_by_key(|(_idx, vector)| std::cmp::Reverse(vector.len()));
+    candidates.into_iter().find_map(|(idx, vector)| {
+        let mut rest = parse_named_element(names, &name[vector.len()..])?;
+        rest.insert(0, Vector(idx));
+        Some(rest)
+    })
+}
+
+fn parse_indexed_element(name: &str) -> Option<Vec<Vector>> {
+    let mut iter = name.chars();
+    if let Some('e') = iter.next() {
+        let number_part: String = iter.take_while(|c| c.is_ascii_digit()).collect();
+        if !number_part.is_empty() && (number_part == "0" || !number_part.starts_with('0')) {
+            let idx = usize::from_str(&number_part).expect("Could not parse usize vector base");
+            let rest = &name[number_part.len() + 1..];
+            if rest.is_empty() {
+                Some(vec![Vector(idx)])
+            } else {
+                let mut idxs = vec![Vector(idx)];
+                idxs.append(&mut parse_indexed_element(rest)?);
+                Some(idxs)
+            }
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+/// Like `try_parse_element`, but in bases of up to ten indexed vectors every digit is a vector
+/// so the compact names from `Element::display` like `e012` are read too.
+pub fn try_parse_basis_element(basis: &Basis, name: &str) -> Option<Vec<Vector>> {
+    if basis.names.is_some() || basis.vectors().len() > 10 || !basis.blade_names.is_empty() {
+        return try_parse_element(basis, name);
+    }
+    let digits = name.strip_prefix('e')?;
+    let mut vs = Vec::new();
+    for group in digits.split('e') {
+        if group.is_empty() || !group.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        vs.extend(group.chars().map(|c| Vector(c as usize - '0' as usize)));
+    }
+    Some(vs)
+}
+
+/// Reads sums and products of constants, symbols, functions and elements like
+/// `3*a*e2 + b^2*e1` or the `Display` form `(3*a)e2 + (b^2)e1`.
+pub fn parse_multivector(basis: &Basis, src: &str) -> Result<MultiVector, String> {
+    let mut parser = Parser {
+        basis,
+        tokens: tokenize(src)?.into_iter().peekable(),
+    };
+    let mv = parser.sum()?;
+    match parser.tokens.next() {
+        None => Ok(mv),
+        Some(token) => Err(format!("Unexpected {:?} after expression", token)),
+    }
+}
+
+/// Reads a scalar expression like `3*a*b^2 - 1/2*c`.
+pub fn parse_symbols(src: &str) -> Result<Symbols, String> {
+    let scalars = Basis::new(0, 0, 0);
+    parse_multivector(&scalars, src)?
+        .scalar_part()
+        .ok_or_else(|| format!("Not a scalar: {}", src))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Integer(BigInt),
+    Ident(String),
+    Punct(char),
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let mut digits = c.to_string();
+            while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                digits.push(*d);
+                chars.next();
+            }
+            let value = BigInt::from_str(&digits).expect("Digits form an integer");
+            tokens.push(Token::Integer(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = c.to_string();
+            while let Some(d) = chars.peek().filter(|d| d.is_alphanumeric() || **d == '_') {
+                name.push(*d);
+                chars.next();
+            }
+            tokens.push(Token::Ident(name));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Punct(c));
+        } else {
+            return Err(format!("Unexpected character '{}'", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    basis: &'a Basis,
+    tokens: Peekable<IntoIter<Token>>,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, punct: char) -> bool {
+        if self.tokens.peek() == Some(&Token::Punct(punct)) {
+            self.tokens.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<MultiVector, String> {
+        let mut mv = self.product()?;
+        loop {
+            if self.eat('+') {
+                mv = mv + self.product()?;
+            } else if self.eat('-') {
+                mv = mv + self.product()?.scale(&lift_integer(-1));
+            } else {
+                return Ok(mv);
+            }
+        }
+    }
+
+    /// Products and quotients, where an element or bracket right after a factor multiplies it.
+    fn product(&mut self) -> Result<MultiVector, String> {
+        let mut mv = self.unary()?;
+        loop {
+            if self.eat('*') {
+                mv = mv.multiply(self.basis, &self.unary()?)?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?.inverse(self.basis)?;
+                mv = mv.multiply(self.basis, &divisor)?;
+            } else if let Some(Token::Ident(_)) | Some(Token::Punct('(')) = self.tokens.peek() {
+                mv = mv.multiply(self.basis, &self.power()?)?;
+            } else {
+                return Ok(mv);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<MultiVector, String> {
+        if self.eat('-') {
+            Ok(self.unary()?.scale(&lift_integer(-1)))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<MultiVector, String> {
+        let base = self.atom()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        let negative = self.eat('-');
+        let exponent = match self.tokens.next() {
+            Some(Token::Integer(n)) => n
+                .to_isize()
+                .ok_or_else(|| "Exponent is too large".to_string())?,
+            token => return Err(format!("Expected an integer exponent, got {:?}", token)),
+        };
+        base.pow(self.basis, if negative { -exponent } else { exponent })
+    }
+
+    fn atom(&mut self) -> Result<MultiVector, String> {
+        match self.tokens.next() {
+            Some(Token::Integer(n)) => Ok(scalar(Symbols::constant(BigRational::from(n)))),
+            Some(Token::Punct('(')) => {
+                let mv = self.sum()?;
+                if !self.eat(')') {
+                    return Err("Expected ')'".to_string());
+                }
+                Ok(mv)
+            }
+            Some(Token::Ident(name)) => self.ident(name),
+            token => Err(format!("Expected a value, got {:?}", token)),
+        }
+    }
+
+    fn ident(&mut self, name: String) -> Result<MultiVector, String> {
+        if let Some(function) = Function::from_name(&name) {
+            if self.eat('(') {
+                return self.apply(function);
+            }
+        }
+        // Elements are outer products of distinct vectors in every basis, which is also their
+        // geometric product in an orthonormal basis
+        match try_parse_basis_element(self.basis, &name) {
+            Some(vs) => vs
+                .iter()
+                .enumerate()
+                .try_fold(scalar(one()), |mv, (idx, v)| {
+                    if v.0 >= self.basis.dimension() {
+                        return Err(format!("Vector index is larger than basis: {}", v.0));
+                    }
+                    if vs[..idx].contains(v) {
+                        return Err(format!("Repeated vector in element: {}", name));
+                    }
+                    mv.outer(self.basis, &vector(*v))
+                }),
+            None => Ok(scalar(Symbols::symbol(&name))),
+        }
+    }
+
+    /// The arguments after the opening bracket, which must all be scalars.
+    fn apply(&mut self, function: Function) -> Result<MultiVector, String> {
+        let mut args = Vec::new();
+        loop {
+            let arg = self
+                .sum()?
+                .scalar_part()
+                .ok_or_else(|| format!("{} needs scalar arguments", function.name()))?;
+            args.push(arg);
+            if self.eat(')') {
+                break;
+            }
+            if !self.eat(',') {
+                return Err(format!(
+                    "Expected ',' or ')' in {} arguments",
+                    function.name()
+                ));
+            }
+        }
+        Ok(scalar(Symbols::try_apply(function, args)?))
+    }
+}
+
+fn one() -> Symbols {
+    Symbols::constant(lift_integer(1))
+}
+
+fn vector(v: Vector) -> MultiVector {
+    MultiVector(std::iter::once((Element::from(v), one())).collect())
+}
+
+fn scalar(sym: Symbols) -> MultiVector {
+    MultiVector::default() + MultiVector(std::iter::once((Element(BTreeSet::new()), sym)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G2: Basis = Basis::new(0, 2, 0);
+
+    const PGA3: Basis = Basis::new(1, 3, 0);
+
+    #[test]
+    fn test_parse_element_names() {
+        assert_eq!(
+            try_parse_element(&PGA3, "e0e12"),
+            Some(vec![Vector(0), Vector(12)])
+        );
+        assert_eq!(try_parse_element(&PGA3, "e01"), None);
+        assert_eq!(
+            try_parse_basis_element(&PGA3, "e013"),
+            Some(vec![Vector(0), Vector(1), Vector(3)])
+        );
+        assert_eq!(
+            try_parse_basis_element(&PGA3, "e1e0"),
+            Some(vec![Vector(1), Vector(0)])
+        );
+        assert_eq!(try_parse_basis_element(&PGA3, "e"), None);
+        assert_eq!(try_parse_basis_element(&PGA3, "ea"), None);
+
+        let names = ["w", "x", "xw", "wy"].iter().map(|name| name.to_string());
+        let named = Basis::from_signature(&[0, 1, 1, 1])
+            .and_then(|basis| basis.with_names(names.collect()))
+            .unwrap();
+        assert_eq!(
+            try_parse_element(&named, "xwx"),
+            Some(vec![Vector(2), Vector(1)])
+        );
+        // `xw` leaves `y`, so backtrack to `x` then `wy`
+        assert_eq!(
+            try_parse_element(&named, "xwy"),
+            Some(vec![Vector(1), Vector(3)])
+        );
+        assert_eq!(try_parse_element(&named, "e1"), None);
+        assert_eq!(
+            parse_multivector(&named, "2*a*wwy - x*x"),
+            parse_multivector(&PGA3, "-1 + 2*a*e03")
+        );
+
+        let digits = ["0", "1", "2", "3"].iter().map(|name| name.to_string());
+        let oriented = PGA3
+            .clone()
+            .with_names(digits.collect())
+            .and_then(|basis| basis.with_orientations(vec![vec![Vector(3), Vector(1)]]))
+            .unwrap();
+        assert_eq!(
+            try_parse_element(&oriented, "e31"),
+            Some(vec![Vector(3), Vector(1)])
+        );
+        assert_eq!(try_parse_element(&oriented, "e"), None);
+        assert_eq!(try_parse_element(&oriented, "ex"), None);
+        assert_eq!(
+            parse_multivector(&oriented, "a*e31 + e0123"),
+            parse_multivector(&PGA3, "-a*e13 + e0123")
+        );
+    }
+
+    #[test]
+    fn test_parse_simple_mult() -> Result<(), String> {
+        // The hand built test in multivector.rs
+        let lhs = parse_multivector(&G2, "5*e1")?;
+        let rhs = parse_multivector(&G2, "3*a*e1 + b^2*e0")?;
+        assert_eq!(
+            lhs.multiply(&G2, &rhs)?,
+            parse_multivector(&G2, "15*a - 5*b^2*e01")?
+        );
+        // Vectors out of order pick up a sign
+        assert_eq!(
+            parse_multivector(&G2, "e1e0")?,
+            parse_multivector(&G2, "-e01")?
+        );
+
+        // Repeated vectors are rejected in every basis, not squared or wedged to zero
+        let int = |x: isize| Symbols::constant(lift_integer(x));
+        let null = Basis::from_metric(crate::basis::Metric::new(vec![
+            vec![int(0), int(-1)],
+            vec![int(-1), int(0)],
+        ])?);
+        for basis in [&G2, &null].iter() {
+            assert!(parse_multivector(basis, "e1e1").is_err());
+            assert!(parse_multivector(basis, "e0e1e0").is_err());
+        }
+        assert_eq!(
+            parse_multivector(&null, "e1e0")?,
+            parse_multivector(&null, "-e01")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_round_trip() -> Result<(), String> {
+        let sources = [
+            "(a*c - 1/2*b)/(c + 1)*e01 + sqrt(a^2 + 1)*e0e1e2e3 - atan2(a, 2)",
+            "-x/(y*z) + (x + y)*e123 + e0",
+            "0",
+        ];
+        for src in sources.iter() {
+            let mv = parse_multivector(&PGA3, src)?;
+            assert_eq!(parse_multivector(&PGA3, &mv.to_string())?, mv, "{}", mv);
+        }
+
+        // Past ten vectors every index has its own `e`
+        let eleven = Basis::new(0, 11, 0);
+        let mv = parse_multivector(&eleven, "a*e1e2 + b*e1e10 - e0e1e2")?;
+        let text = mv.display(&eleven).to_string();
+        assert_eq!(text, "(a)e1e2 + (b)e1e10 + (-1)e0e1e2");
+        assert_eq!(parse_multivector(&eleven, &text)?, mv);
+        let oriented = PGA3
+            .clone()
+            .with_orientations(vec![vec![Vector(3), Vector(1)]])?;
+        let mv = parse_multivector(&oriented, "a*e31 + b*e013")?;
+        let text = mv.display(&oriented).to_string();
+        assert_eq!(text, "(a)e31 + (b)e013");
+        assert_eq!(parse_multivector(&oriented, &text)?, mv);
+        let names = ["w", "x", "y", "z"].iter().map(|name| name.to_string());
+        let named = PGA3.clone().with_names(names.collect())?;
+        // With names `e12` is only a symbol, so blades must be written with the names
+        let mv = parse_multivector(&named, "a*xy - wxyz + e12")?;
+        let text = mv.display(&named).to_string();
+        assert_eq!(text, "(e12) + (a)xy + (-1)wxyz");
+        assert_eq!(parse_multivector(&named, &text)?, mv);
+
+        let syms = parse_symbols("3*a*b^2 - 1/2*c")?;
+        assert_eq!(syms.to_string(), "3*a*b^2 - 1/2*c");
+        assert_eq!(Symbols::from_str(&syms.to_string())?, syms);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_multivector(&G2, "e3").is_err());
+        assert!(parse_multivector(&G2, "(a + b").is_err());
+        assert!(parse_multivector(&G2, "a $ b").is_err());
+        assert!(parse_multivector(&G2, "sqrt(e0)").is_err());
+        assert!(parse_multivector(&G2, "atan2(a)").is_err());
+        assert!(parse_symbols("a*e0").is_err());
+    }
+}
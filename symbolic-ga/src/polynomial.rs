@@ -0,0 +1,521 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use num::rational::BigRational;
+use num::{One, Signed, Zero};
+
+use crate::function::Function;
+use crate::symbols::{lift_integer, Symbols};
+
+/// A scalar variable, either named or an opaque function applied to other scalar expressions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Symbol {
+    Named(String),
+    Apply(Function, Vec<Symbols>),
+}
+
+impl Symbol {
+    pub fn is_nonnegative(&self) -> bool {
+        match self {
+            Symbol::Named(_) => false,
+            Symbol::Apply(function, _args) => function.is_nonnegative(),
+        }
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Symbol {
+        Symbol::Named(name.to_string())
+    }
+}
+
+/// Named symbols as they are, applications like `sqrt(a*a + b)`.
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Symbol::Named(name) => write!(f, "{}", name),
+            Symbol::Apply(function, args) => {
+                write!(f, "{}(", function.name())?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+pub type SymbolPowers = BTreeMap<Symbol, usize>;
+
+/// A polynomial in commuting symbols with exact rational coefficients. Zero coefficients are never
+/// stored, so the zero polynomial is the empty map.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Polynomial(pub BTreeMap<SymbolPowers, BigRational>);
+
+impl std::ops::Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, Polynomial(rhs_powers): &Polynomial) -> Polynomial {
+        let Polynomial(lhs_powers) = self;
+        lhs_powers
+            .iter()
+            .flat_map(|(lhs_power, lhs_scale)| {
+                rhs_powers.iter().map(move |(rhs_power, rhs_scale)| {
+                    (
+                        multiply_symbol_powers(lhs_power, rhs_power),
+                        lhs_scale * rhs_scale,
+                    )
+                })
+            })
+            .fold(Polynomial::default(), |prev, (pwr, scale)| {
+                prev.add_scaled_power(scale, pwr)
+            })
+    }
+}
+
+impl std::ops::Add for Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: Polynomial) -> Polynomial {
+        self.0
+            .into_iter()
+            .chain(rhs.0)
+            .fold(Polynomial::default(), |prev, (pows, scale)| {
+                prev.add_scaled_power(scale, pows)
+            })
+    }
+}
+
+/// Terms from the leading one down, like `3*a*b^2 - 1/2*c`.
+impl std::fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        for (idx, (powers, scale)) in self.terms().into_iter().enumerate() {
+            match (idx, scale.is_negative()) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+            let scale = scale.abs();
+            if powers.is_empty() {
+                write!(f, "{}", scale)?;
+                continue;
+            }
+            if !scale.is_one() {
+                write!(f, "{}*", scale)?;
+            }
+            for (idx, (sym, pow)) in powers.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "*")?;
+                }
+                match pow {
+                    1 => write!(f, "{}", sym)?,
+                    pow => write!(f, "{}^{}", sym, pow)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Polynomial {
+    pub fn constant(value: BigRational) -> Polynomial {
+        Polynomial::default().add_scaled_power(value, BTreeMap::new())
+    }
+
+    pub fn symbol(sym: Symbol) -> Polynomial {
+        Polynomial(
+            vec![(vec![(sym, 1)].into_iter().collect(), lift_integer(1))]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.constant_value().is_some_and(|c| c.is_one())
+    }
+
+    /// The value of a polynomial without any symbols.
+    pub fn constant_value(&self) -> Option<BigRational> {
+        match self.0.iter().next() {
+            None => Some(lift_integer(0)),
+            Some((powers, scale)) if powers.is_empty() && self.0.len() == 1 => Some(scale.clone()),
+            _ => None,
+        }
+    }
+
+    fn add_scaled_power(mut self, scale: BigRational, power: SymbolPowers) -> Polynomial {
+        let existing = self.0.remove(&power).unwrap_or_else(|| lift_integer(0));
+        let sum = existing + scale;
+        if !sum.is_zero() {
+            self.0.insert(power, sum);
+        }
+        self
+    }
+
+    pub fn scale(&self, factor: &BigRational) -> Polynomial {
+        self * &Polynomial::constant(factor.clone())
+    }
+
+    /// The sign for all values of the symbols, known when every term is a product of even powers
+    /// and nonnegative functions, and all coefficients agree in sign. Zero is treated as either
+    /// sign.
+    pub fn definite_sign(&self) -> Option<Ordering> {
+        if self.is_zero() {
+            return Some(Ordering::Equal);
+        }
+        if self.0.keys().any(|powers| {
+            powers
+                .iter()
+                .any(|(sym, pow)| pow % 2 == 1 && !sym.is_nonnegative())
+        }) {
+            return None;
+        }
+        let zero = lift_integer(0);
+        let mut signs = self.0.values().map(|scale| scale.cmp(&zero));
+        let first = signs.next()?;
+        if signs.all(|sign| sign == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    /// The greatest term in lexicographic order, see `monomial_cmp`.
+    pub fn leading_term(&self) -> Option<(&SymbolPowers, &BigRational)> {
+        self.0
+            .iter()
+            .max_by(|(lhs, _), (rhs, _)| monomial_cmp(lhs, rhs))
+    }
+
+    /// All terms, from the leading one down.
+    pub fn terms(&self) -> Vec<(&SymbolPowers, &BigRational)> {
+        let mut terms: Vec<_> = self.0.iter().collect();
+        terms.sort_by(|(lhs, _), (rhs, _)| monomial_cmp(rhs, lhs));
+        terms
+    }
+
+    /// Scales so the leading coefficient is one, zero stays zero.
+    pub fn monic(&self) -> Polynomial {
+        match self.leading_term() {
+            Some((_powers, scale)) => self.scale(&scale.recip()),
+            None => Polynomial::default(),
+        }
+    }
+
+    pub fn symbols(&self) -> BTreeSet<Symbol> {
+        self.0
+            .keys()
+            .flat_map(|powers| powers.keys().cloned())
+            .collect()
+    }
+
+    pub fn degree_in(&self, sym: &Symbol) -> usize {
+        self.0
+            .keys()
+            .map(|powers| powers.get(sym).cloned().unwrap_or(0))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Views the polynomial as univariate in `sym`, with polynomial coefficients.
+    fn coefficients_in(&self, sym: &Symbol) -> BTreeMap<usize, Polynomial> {
+        let mut coefficients: BTreeMap<usize, Polynomial> = BTreeMap::new();
+        for (powers, scale) in self.0.iter() {
+            let mut powers = powers.clone();
+            let power = powers.remove(sym).unwrap_or(0);
+            let coefficient = coefficients.remove(&power).unwrap_or_default();
+            coefficients.insert(power, coefficient.add_scaled_power(scale.clone(), powers));
+        }
+        coefficients
+    }
+
+    /// Divides when the result is also a polynomial.
+    pub fn divide_exact(&self, divisor: &Polynomial) -> Option<Polynomial> {
+        let (divisor_powers, divisor_scale) = divisor.leading_term()?;
+
+        let mut quotient = Polynomial::default();
+        let mut remainder = self.clone();
+
+        while let Some((powers, scale)) = remainder.leading_term() {
+            let powers = divide_symbol_powers(powers, divisor_powers)?;
+            let term = Polynomial::default().add_scaled_power(scale / divisor_scale, powers);
+            remainder = remainder + (&term * divisor).scale(&lift_integer(-1));
+            quotient = quotient + term;
+        }
+
+        Some(quotient)
+    }
+
+    /// The monic greatest common divisor, found one symbol at a time with primitive pseudo
+    /// remainder sequences.
+    pub fn gcd(&self, rhs: &Polynomial) -> Polynomial {
+        if self.is_zero() {
+            return rhs.monic();
+        }
+        if rhs.is_zero() {
+            return self.monic();
+        }
+
+        let sym = match self.symbols().union(&rhs.symbols()).next() {
+            Some(sym) => sym.clone(),
+            None => return Polynomial::constant(lift_integer(1)),
+        };
+
+        let (lhs_content, lhs_primitive) = self.content_in(&sym);
+        let (rhs_content, rhs_primitive) = rhs.content_in(&sym);
+
+        let content = lhs_content.gcd(&rhs_content);
+        let primitive = primitive_gcd(lhs_primitive, rhs_primitive, &sym);
+
+        (&content * &primitive).monic()
+    }
+
+    /// Splits into the gcd of the coefficients in `sym` and the remaining primitive part.
+    fn content_in(&self, sym: &Symbol) -> (Polynomial, Polynomial) {
+        let content = self
+            .coefficients_in(sym)
+            .values()
+            .fold(Polynomial::default(), |content, coefficient| {
+                content.gcd(coefficient)
+            });
+        let primitive = self
+            .divide_exact(&content)
+            .expect("Content always divides the polynomial");
+        (content, primitive)
+    }
+
+    fn pseudo_remainder(&self, divisor: &Polynomial, sym: &Symbol) -> Polynomial {
+        let divisor_degree = divisor.degree_in(sym);
+        let divisor_leading = divisor.coefficients_in(sym).remove(&divisor_degree);
+        let divisor_leading = divisor_leading.expect("Divisor has a leading coefficient");
+
+        let mut remainder = self.clone();
+        while !remainder.is_zero() && remainder.degree_in(sym) >= divisor_degree {
+            let degree = remainder.degree_in(sym);
+            let leading = remainder.coefficients_in(sym).remove(&degree);
+            let leading = leading.expect("Remainder has a leading coefficient");
+            let shift = Polynomial(
+                vec![(
+                    vec![(sym.clone(), degree - divisor_degree)]
+                        .into_iter()
+                        .filter(|(_sym, pow)| *pow > 0)
+                        .collect(),
+                    lift_integer(1),
+                )]
+                .into_iter()
+                .collect(),
+            );
+            remainder = &divisor_leading * &remainder
+                + (&(&leading * &shift) * divisor).scale(&lift_integer(-1));
+        }
+        remainder
+    }
+}
+
+fn primitive_gcd(lhs: Polynomial, rhs: Polynomial, sym: &Symbol) -> Polynomial {
+    let (mut lhs, mut rhs) = if lhs.degree_in(sym) >= rhs.degree_in(sym) {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+
+    loop {
+        if rhs.is_zero() {
+            return lhs.content_in(sym).1;
+        }
+        if rhs.degree_in(sym) == 0 {
+            return Polynomial::constant(lift_integer(1));
+        }
+        let remainder = lhs.pseudo_remainder(&rhs, sym);
+        lhs = rhs;
+        rhs = if remainder.is_zero() {
+            remainder
+        } else {
+            remainder.content_in(sym).1
+        };
+    }
+}
+
+/// Lexicographic order on monomials, where symbols earlier in the alphabet are larger.
+pub fn monomial_cmp(lhs: &SymbolPowers, rhs: &SymbolPowers) -> Ordering {
+    let mut lhs = lhs.iter().peekable();
+    let mut rhs = rhs.iter().peekable();
+
+    loop {
+        match (lhs.peek(), rhs.peek()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some((lhs_sym, lhs_pow)), Some((rhs_sym, rhs_pow))) => match lhs_sym.cmp(rhs_sym) {
+                Ordering::Less => return Ordering::Greater,
+                Ordering::Greater => return Ordering::Less,
+                Ordering::Equal => match lhs_pow.cmp(rhs_pow) {
+                    Ordering::Equal => {
+                        lhs.next();
+                        rhs.next();
+                    }
+                    ordering => return ordering,
+                },
+            },
+        }
+    }
+}
+
+pub fn multiply_symbol_powers(lhs: &SymbolPowers, rhs: &SymbolPowers) -> SymbolPowers {
+    lhs.iter()
+        .chain(rhs.iter())
+        .fold(BTreeMap::new(), |mut prev, (sym, pow)| {
+            let existing = prev.remove(sym).unwrap_or(0);
+            prev.insert(sym.clone(), existing + pow);
+            prev
+        })
+}
+
+pub fn divide_symbol_powers(lhs: &SymbolPowers, rhs: &SymbolPowers) -> Option<SymbolPowers> {
+    let mut quotient = lhs.clone();
+    for (sym, pow) in rhs.iter() {
+        let existing = quotient.remove(sym)?;
+        match existing.cmp(pow) {
+            Ordering::Less => return None,
+            Ordering::Equal => {}
+            Ordering::Greater => {
+                quotient.insert(sym.clone(), existing - pow);
+            }
+        }
+    }
+    Some(quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(terms: &[(isize, &[(&str, usize)])]) -> Polynomial {
+        terms
+            .iter()
+            .fold(Polynomial::default(), |prev, (scale, powers)| {
+                let powers = powers.iter().map(|(s, p)| (Symbol::from(*s), *p)).collect();
+                prev.add_scaled_power(lift_integer(*scale), powers)
+            })
+    }
+
+    #[test]
+    fn test_simple_multiply_symbol_powers() {
+        let lhs: SymbolPowers = vec![("x".into(), 2), ("y".into(), 3)].into_iter().collect();
+        let rhs: SymbolPowers = vec![("y".into(), 4), ("z".into(), 5)].into_iter().collect();
+
+        assert_eq!(
+            multiply_symbol_powers(&lhs, &rhs),
+            vec![("x".into(), 2), ("y".into(), 3 + 4), ("z".into(), 5)]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_mult_power_increase() {
+        // (2 + x) * (3 + xx) = 6 + 2xx + 3x + xxx
+        let lhs = poly(&[(1, &[("x", 1)]), (2, &[])]);
+        let rhs = poly(&[(1, &[("x", 2)]), (3, &[])]);
+
+        let expected = poly(&[
+            (1, &[("x", 3)]),
+            (2, &[("x", 2)]),
+            (3, &[("x", 1)]),
+            (6, &[]),
+        ]);
+
+        assert_eq!(&lhs * &rhs, expected);
+    }
+
+    #[test]
+    fn test_lexicographic_order() {
+        let x = poly(&[(1, &[("x", 1)])]);
+        let xy = poly(&[(1, &[("x", 1), ("y", 1)])]);
+        let y = poly(&[(1, &[("y", 1)])]);
+        let yy = poly(&[(1, &[("y", 2)])]);
+
+        let powers = |p: &Polynomial| p.0.keys().next().unwrap().clone();
+        assert_eq!(monomial_cmp(&powers(&xy), &powers(&y)), Ordering::Greater);
+        assert_eq!(monomial_cmp(&powers(&x), &powers(&yy)), Ordering::Greater);
+        assert_eq!(
+            monomial_cmp(&powers(&y), &BTreeMap::new()),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_definite_sign() {
+        assert_eq!(
+            poly(&[(-1, &[("a", 2)]), (-2, &[("b", 2), ("c", 4)])]).definite_sign(),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            poly(&[(1, &[("a", 2)]), (-1, &[("b", 2)])]).definite_sign(),
+            None
+        );
+        assert_eq!(poly(&[(1, &[("a", 1)])]).definite_sign(), None);
+        assert_eq!(Polynomial::default().definite_sign(), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn test_divide_exact() {
+        // (aa - bb) / (a - b) = a + b, but a + b does not divide aa + bb
+        let numer = poly(&[(1, &[("a", 2)]), (-1, &[("b", 2)])]);
+        let denom = poly(&[(1, &[("a", 1)]), (-1, &[("b", 1)])]);
+
+        assert_eq!(
+            numer.divide_exact(&denom),
+            Some(poly(&[(1, &[("a", 1)]), (1, &[("b", 1)])]))
+        );
+        assert_eq!(
+            poly(&[(1, &[("a", 2)]), (1, &[("b", 2)])])
+                .divide_exact(&poly(&[(1, &[("a", 1)]), (1, &[("b", 1)])])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gcd() {
+        // gcd((a + b)(a - 2c) xx, 3(a + b)(b + c) x) = (a + b) x
+        let a_plus_b = poly(&[(1, &[("a", 1)]), (1, &[("b", 1)])]);
+        let lhs = &(&a_plus_b * &poly(&[(1, &[("a", 1)]), (-2, &[("c", 1)])]))
+            * &poly(&[(1, &[("x", 2)])]);
+        let rhs = &(&a_plus_b * &poly(&[(3, &[("b", 1)]), (3, &[("c", 1)])]))
+            * &poly(&[(1, &[("x", 1)])]);
+
+        let expected = &a_plus_b * &poly(&[(1, &[("x", 1)])]);
+        assert_eq!(lhs.gcd(&rhs), expected);
+        assert_eq!(rhs.gcd(&lhs), expected);
+    }
+
+    #[test]
+    fn test_gcd_of_coprime() {
+        let lhs = poly(&[(1, &[("a", 2)]), (1, &[("b", 2)])]);
+        let rhs = poly(&[(2, &[("a", 1)]), (1, &[])]);
+
+        assert_eq!(lhs.gcd(&rhs), poly(&[(1, &[])]));
+        assert_eq!(lhs.gcd(&Polynomial::default()), lhs);
+    }
+
+    #[test]
+    fn test_display() {
+        let third = poly(&[(3, &[("a", 1), ("b", 2)])]);
+        let half_c = Polynomial::symbol("c".into()).scale(&BigRational::new((-1).into(), 2.into()));
+        assert_eq!((third + half_c).to_string(), "3*a*b^2 - 1/2*c");
+        assert_eq!(poly(&[(-1, &[("b", 1)]), (1, &[])]).to_string(), "-b + 1");
+        assert_eq!(Polynomial::default().to_string(), "0");
+    }
+}
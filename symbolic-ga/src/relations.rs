@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use num::rational::BigRational;
+
+use crate::polynomial::{divide_symbol_powers, monomial_cmp, Polynomial, SymbolPowers};
+use crate::symbols::{lift_integer, Symbols};
+
+/// Side relations `p = 0` the symbols are known to satisfy, kept as a reduced Gröbner basis in
+/// the order of `monomial_cmp` so every polynomial has a unique remainder.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Relations(Vec<Polynomial>);
+
+impl Relations {
+    /// Relations from expressions equal to zero, only their numerators matter.
+    pub fn new(zeros: &[Symbols]) -> Result<Relations, String> {
+        let generators = zeros
+            .iter()
+            .map(|zero| zero.numer.monic())
+            .filter(|poly| !poly.is_zero())
+            .collect();
+        let basis = groebner_basis(generators);
+        if basis.iter().any(|poly| poly.constant_value().is_some()) {
+            return Err("Relations are inconsistent, they imply 1 = 0".to_string());
+        }
+        Ok(Relations(basis))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn polynomials(&self) -> &[Polynomial] {
+        &self.0
+    }
+
+    /// The normal form, equal to `poly` whenever the relations hold.
+    pub fn remainder(&self, poly: &Polynomial) -> Polynomial {
+        remainder(&self.0, poly)
+    }
+
+    /// Whether the relations imply `syms` is zero.
+    pub fn implies_zero(&self, syms: &Symbols) -> bool {
+        self.remainder(&syms.numer).is_zero()
+    }
+}
+
+/// Buchberger's algorithm, skipping pairs with coprime leading monomials.
+fn groebner_basis(mut basis: Vec<Polynomial>) -> Vec<Polynomial> {
+    let mut pairs: Vec<(usize, usize)> = (0..basis.len())
+        .flat_map(|j| (0..j).map(move |i| (i, j)))
+        .collect();
+
+    while let Some((i, j)) = pairs.pop() {
+        let (lhs, rhs) = (&basis[i], &basis[j]);
+        if coprime(leading_powers(lhs), leading_powers(rhs)) {
+            continue;
+        }
+        let rest = remainder(&basis, &s_polynomial(lhs, rhs));
+        if !rest.is_zero() {
+            let k = basis.len();
+            basis.push(rest.monic());
+            pairs.extend((0..k).map(|i| (i, k)));
+        }
+    }
+
+    reduce_basis(basis)
+}
+
+/// Drops generators whose leading monomial is a multiple of another's, then reduces each by the
+/// rest so the basis is unique.
+fn reduce_basis(basis: Vec<Polynomial>) -> Vec<Polynomial> {
+    let mut minimal: Vec<Polynomial> = Vec::new();
+    for poly in basis {
+        let powers = leading_powers(&poly).clone();
+        if minimal
+            .iter()
+            .any(|other| divide_symbol_powers(&powers, leading_powers(other)).is_some())
+        {
+            continue;
+        }
+        minimal.retain(|other| divide_symbol_powers(leading_powers(other), &powers).is_none());
+        minimal.push(poly);
+    }
+
+    let mut reduced: Vec<Polynomial> = (0..minimal.len())
+        .map(|i| {
+            let others: Vec<Polynomial> = minimal
+                .iter()
+                .enumerate()
+                .filter(|(j, _poly)| *j != i)
+                .map(|(_j, poly)| poly.clone())
+                .collect();
+            remainder(&others, &minimal[i]).monic()
+        })
+        .collect();
+    reduced.sort_by(|lhs, rhs| monomial_cmp(leading_powers(rhs), leading_powers(lhs)));
+    reduced
+}
+
+/// Cancels the leading terms of both polynomials against their least common multiple.
+fn s_polynomial(lhs: &Polynomial, rhs: &Polynomial) -> Polynomial {
+    let (lhs_powers, lhs_scale) = lhs.leading_term().expect("Basis polynomials are nonzero");
+    let (rhs_powers, rhs_scale) = rhs.leading_term().expect("Basis polynomials are nonzero");
+    let lcm = lcm_symbol_powers(lhs_powers, rhs_powers);
+
+    let lhs_shift = divide_symbol_powers(&lcm, lhs_powers).expect("Lcm is a multiple");
+    let rhs_shift = divide_symbol_powers(&lcm, rhs_powers).expect("Lcm is a multiple");
+
+    &term(lhs_shift, lhs_scale.recip()) * lhs
+        + (&term(rhs_shift, rhs_scale.recip()) * rhs).scale(&lift_integer(-1))
+}
+
+/// Repeatedly cancels any term divisible by a leading monomial of `basis`.
+fn remainder(basis: &[Polynomial], poly: &Polynomial) -> Polynomial {
+    let mut rest = poly.clone();
+    let mut result = Polynomial::default();
+
+    while let Some((powers, scale)) = rest.leading_term() {
+        let (powers, scale) = (powers.clone(), scale.clone());
+        let divisor = basis.iter().find_map(|divisor| {
+            let (divisor_powers, divisor_scale) = divisor.leading_term()?;
+            let shift = divide_symbol_powers(&powers, divisor_powers)?;
+            Some((divisor, shift, divisor_scale.clone()))
+        });
+        match divisor {
+            Some((divisor, shift, divisor_scale)) => {
+                let quotient = term(shift, scale / divisor_scale);
+                rest = rest + (&quotient * divisor).scale(&lift_integer(-1));
+            }
+            None => {
+                let leading = term(powers, scale);
+                result = result + leading.clone();
+                rest = rest + leading.scale(&lift_integer(-1));
+            }
+        }
+    }
+
+    result
+}
+
+fn term(powers: SymbolPowers, scale: BigRational) -> Polynomial {
+    Polynomial(std::iter::once((powers, scale)).collect())
+}
+
+fn leading_powers(poly: &Polynomial) -> &SymbolPowers {
+    poly.leading_term()
+        .expect("Basis polynomials are nonzero")
+        .0
+}
+
+fn coprime(lhs: &SymbolPowers, rhs: &SymbolPowers) -> bool {
+    lhs.keys().all(|sym| !rhs.contains_key(sym))
+}
+
+fn lcm_symbol_powers(lhs: &SymbolPowers, rhs: &SymbolPowers) -> SymbolPowers {
+    let mut lcm: SymbolPowers = BTreeMap::new();
+    for (sym, pow) in lhs.iter().chain(rhs.iter()) {
+        let existing = lcm.get(sym).cloned().unwrap_or(0);
+        lcm.insert(sym.clone(), existing.max(*pow));
+    }
+    lcm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sums of `scale * symbol * symbol ...`, where an empty product is the constant 1.
+    fn sum(terms: &[(isize, &[&str])]) -> Symbols {
+        terms
+            .iter()
+            .fold(Symbols::zero(), |prev, (scale, symbols)| {
+                let product = symbols
+                    .iter()
+                    .fold(Symbols::constant(lift_integer(*scale)), |prod, name| {
+                        &prod * &Symbols::symbol(name)
+                    });
+                prev + product
+            })
+    }
+
+    #[test]
+    fn test_unit_circle_remainder() -> Result<(), String> {
+        // a^2 + b^2 = 1
+        let relations = Relations::new(&[sum(&[(1, &["a", "a"]), (1, &["b", "b"]), (-1, &[])])])?;
+
+        let remainder = relations.remainder(&sum(&[(3, &["a", "a"]), (3, &["b", "b"])]).numer);
+        assert_eq!(remainder, sum(&[(3, &[])]).numer);
+
+        // a^2 - b^2 becomes 1 - 2 b^2
+        let remainder = relations.remainder(&sum(&[(1, &["a", "a"]), (-1, &["b", "b"])]).numer);
+        assert_eq!(remainder, sum(&[(1, &[]), (-2, &["b", "b"])]).numer);
+
+        assert!(relations.implies_zero(&sum(&[
+            (1, &["a", "a", "c"]),
+            (1, &["b", "b", "c"]),
+            (-1, &["c"])
+        ])));
+        assert!(!relations.implies_zero(&sum(&[(1, &["a"])])));
+        Ok(())
+    }
+
+    #[test]
+    fn test_groebner_basis_is_reduced() -> Result<(), String> {
+        // a^2 + b^2 = 1 and a = b, so a = b and b^2 = 1/2
+        let relations = Relations::new(&[
+            sum(&[(1, &["a", "a"]), (1, &["b", "b"]), (-1, &[])]),
+            sum(&[(1, &["a"]), (-1, &["b"])]),
+        ])?;
+        let half = Symbols::constant(BigRational::new(1.into(), 2.into()));
+        assert_eq!(
+            relations.polynomials(),
+            &[
+                sum(&[(1, &["a"]), (-1, &["b"])]).numer,
+                (sum(&[(1, &["b", "b"])]) + half.invert()).numer,
+            ]
+        );
+
+        assert!(Relations::new(&[sum(&[(1, &["a"])]), sum(&[(1, &["a"]), (-1, &[])])]).is_err());
+        assert!(Relations::new(&[]).is_ok_and(|relations| relations.is_empty()));
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+//! Serde support for the types without a derived form. Rationals are written as exact
+//! strings like `"-3/2"`, polynomials and multivectors as lists of terms so that formats
+//! with string keys like JSON can hold them. Types with invariants are read in their derived
+//! form and checked by their constructors.
+
+use std::str::FromStr;
+
+use num::rational::BigRational;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::basis::{Basis, Metric, Vector};
+use crate::conformal::Conformal;
+use crate::element::Element;
+use crate::function::Function;
+use crate::multivector::MultiVector;
+use crate::polynomial::{Polynomial, Symbol};
+use crate::symbols::Symbols;
+
+#[derive(Serialize, Deserialize)]
+struct Term {
+    scale: Rational,
+    powers: Vec<(Symbol, usize)>,
+}
+
+struct Rational(BigRational);
+
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        BigRational::from_str(&src)
+            .map(Rational)
+            .map_err(|_| D::Error::custom(format!("Invalid rational: {}", src)))
+    }
+}
+
+impl Serialize for Polynomial {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|(powers, scale)| {
+            Term {
+                scale: Rational(scale.clone()),
+                powers: powers
+                    .iter()
+                    .map(|(sym, pow)| (sym.clone(), *pow))
+                    .collect(),
+            }
+        }))
+    }
+}
+
+/// Like terms are collected and zero terms dropped.
+impl<'de> Deserialize<'de> for Polynomial {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Polynomial, D::Error> {
+        let terms = Vec::<Term>::deserialize(deserializer)?;
+        Ok(terms.into_iter().fold(Polynomial::default(), |prev, term| {
+            let powers = term.powers.into_iter().filter(|(_sym, pow)| *pow > 0);
+            let term = Polynomial(std::iter::once((powers.collect(), term.scale.0)).collect());
+            prev + term
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+enum SymbolFields {
+    Named(String),
+    Apply(Function, Vec<Symbols>),
+}
+
+/// Function applications must have as many arguments as the function takes.
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Symbol, D::Error> {
+        match SymbolFields::deserialize(deserializer)? {
+            SymbolFields::Named(name) => Ok(Symbol::Named(name)),
+            SymbolFields::Apply(function, args) => {
+                function.check_arity(args.len()).map_err(D::Error::custom)?;
+                Ok(Symbol::Apply(function, args))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SymbolsFields {
+    numer: Polynomial,
+    denom: Polynomial,
+}
+
+/// Reduced to lowest terms, fails for a zero denominator.
+impl<'de> Deserialize<'de> for Symbols {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Symbols, D::Error> {
+        let fields = SymbolsFields::deserialize(deserializer)?;
+        Symbols::new(fields.numer, fields.denom).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+struct MetricRows(Vec<Vec<Symbols>>);
+
+impl<'de> Deserialize<'de> for Metric {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Metric, D::Error> {
+        let MetricRows(rows) = MetricRows::deserialize(deserializer)?;
+        Metric::new(rows).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+struct BasisFields {
+    zero: usize,
+    positive: usize,
+    negative: usize,
+    metric: Option<Metric>,
+    names: Option<Vec<String>>,
+    orientations: Vec<Vec<Vector>>,
+    blade_names: Vec<(Vec<Vector>, String)>,
+    conformal: bool,
+}
+
+/// Names and orientations are checked as when building the basis, and a conformal basis must
+/// have the shape `Conformal::basis` gives.
+impl<'de> Deserialize<'de> for Basis {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Basis, D::Error> {
+        let fields = BasisFields::deserialize(deserializer)?;
+        let basis = Basis {
+            metric: fields.metric,
+            conformal: fields.conformal,
+            ..Basis::new(fields.zero, fields.positive, fields.negative)
+        };
+        let basis = match fields.names {
+            Some(names) => basis.with_names(names).map_err(D::Error::custom)?,
+            None => basis,
+        };
+        // Named blades add their own orientations, which the others must not change
+        let blade_names = fields.blade_names;
+        let orientations = fields
+            .orientations
+            .into_iter()
+            .filter(|blade| blade_names.iter().all(|(named, _name)| named != blade))
+            .collect();
+        let basis = basis
+            .with_blade_names(blade_names)
+            .and_then(|basis| basis.with_orientations(orientations))
+            .map_err(D::Error::custom)?;
+        if basis.conformal && Conformal::from_basis(&basis).is_none() {
+            return Err(D::Error::custom("Not a conformal basis"));
+        }
+        Ok(basis)
+    }
+}
+
+impl Serialize for MultiVector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+/// Repeated elements are summed and zero components dropped.
+impl<'de> Deserialize<'de> for MultiVector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MultiVector, D::Error> {
+        let components = Vec::<(Element, Symbols)>::deserialize(deserializer)?;
+        Ok(components
+            .into_iter()
+            .fold(MultiVector::default(), |prev, component| {
+                prev + MultiVector(std::iter::once(component).collect())
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_multivector;
+
+    const PGA3: Basis = Basis::new(1, 3, 0);
+
+    fn round_trip<T>(value: &T) -> Result<T, String>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), String> {
+        let basis = round_trip(&PGA3)?;
+        assert_eq!(
+            (basis.zero, basis.positive, basis.negative),
+            (PGA3.zero, PGA3.positive, PGA3.negative)
+        );
+        assert_eq!(round_trip(&Vector(3))?, Vector(3));
+
+        let mv = parse_multivector(
+            &PGA3,
+            "(a*c - 1/3*b)/(c + 7)*e01 + sqrt(a^2 + 1)*e0123 - 123456789012345678901/2",
+        )?;
+        assert_eq!(round_trip(&mv)?, mv);
+        for (elem, sym) in mv.0.iter() {
+            assert_eq!(&round_trip(elem)?, elem);
+            assert_eq!(&round_trip(sym)?, sym);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_form() -> Result<(), String> {
+        let mv = parse_multivector(&PGA3, "-3/2*a^2*e12")?;
+        assert_eq!(
+            serde_json::to_string(&mv).map_err(|e| e.to_string())?,
+            concat!(
+                r#"[[[1,2],{"numer":[{"scale":"-3/2","powers":[[{"Named":"a"},2]]}],"#,
+                r#""denom":[{"scale":"1","powers":[]}]}]]"#
+            )
+        );
+        assert!(serde_json::from_str::<Polynomial>(r#"[{"scale":"1/0","powers":[]}]"#).is_err());
+        Ok(())
+    }
+
+    fn from_value<T>(value: serde_json::Value) -> Result<T, String>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_symbols_are_reduced() -> Result<(), String> {
+        let two = serde_json::json!([{"scale": "2", "powers": []}]);
+        let a = serde_json::json!([{"scale": "1", "powers": [[{"Named": "a"}, 1]]}]);
+        let halves: Symbols = from_value(serde_json::json!({"numer": a, "denom": two}))?;
+        assert_eq!(halves, Symbols::from_str("1/2*a")?);
+        let one: Symbols = from_value(serde_json::json!({"numer": two, "denom": two}))?;
+        assert_eq!(one, Symbols::from_str("1")?);
+        assert!(from_value::<Symbols>(serde_json::json!({"numer": two, "denom": []})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_arity_is_checked() -> Result<(), String> {
+        let atan2 = Symbols::from_str("atan2(a, b)")?;
+        let json = serde_json::to_value(&atan2).map_err(|e| e.to_string())?;
+        assert_eq!(from_value::<Symbols>(json.clone())?, atan2);
+
+        let apply = &json["numer"][0]["powers"][0][0];
+        assert_eq!(
+            from_value::<Symbol>(apply.clone())?.to_string(),
+            "atan2(a, b)"
+        );
+        let mut one_arg = apply.clone();
+        one_arg["Apply"][1]
+            .as_array_mut()
+            .ok_or("No arguments")?
+            .pop();
+        assert!(from_value::<Symbol>(one_arg.clone()).is_err());
+
+        let mut json = json;
+        json["numer"][0]["powers"][0][0] = one_arg;
+        assert!(from_value::<Symbols>(json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_basis_is_checked() -> Result<(), String> {
+        let names = ["0", "1", "2", "3"].iter().map(|name| name.to_string());
+        let named = PGA3
+            .clone()
+            .with_names(names.collect())?
+            .with_orientations(vec![vec![Vector(3), Vector(1)]])?
+            .with_blade_names(vec![(vec![Vector(2), Vector(1)], "k".to_string())])?;
+        let basis = round_trip(&named)?;
+        assert_eq!(basis.names, named.names);
+        assert_eq!(basis.orientations, named.orientations);
+        assert_eq!(basis.blade_names, named.blade_names);
+        let null = Conformal::null(2).basis();
+        assert_eq!(round_trip(&null)?.metric, null.metric);
+        assert!(round_trip(&null)?.conformal);
+
+        let json = serde_json::to_value(&named).map_err(|e| e.to_string())?;
+        let with = |field: &str, value: serde_json::Value| {
+            let mut json = json.clone();
+            json[field] = value;
+            from_value::<Basis>(json)
+        };
+        assert!(with("names", serde_json::json!(["0", "1", "1", "3"])).is_err());
+        assert!(with("names", serde_json::json!(["0", "1", "2"])).is_err());
+        assert!(with("orientations", serde_json::json!([[3, 3]])).is_err());
+        assert!(with("orientations", serde_json::json!([[3, 1], [1, 3]])).is_err());
+        assert!(with("blade_names", serde_json::json!([[[2, 1], "e1"]])).is_err());
+        assert!(with("conformal", serde_json::json!(true)).is_err());
+
+        // Not symmetric
+        let mut json = serde_json::to_value(&null).map_err(|e| e.to_string())?;
+        json["metric"][0][1] = json["metric"][0][0].clone();
+        assert!(from_value::<Basis>(json.clone()).is_err());
+        assert!(from_value::<Metric>(json["metric"].clone()).is_err());
+        Ok(())
+    }
+}
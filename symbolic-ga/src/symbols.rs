@@ -1,38 +1,82 @@
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
 use num::bigint::BigInt;
 use num::rational::BigRational;
-use num::Zero;
+use num::ToPrimitive;
 
-pub type Symbol = String;
+use crate::function::Function;
+use crate::polynomial::Polynomial;
+pub use crate::polynomial::{Symbol, SymbolPowers};
+use crate::relations::Relations;
 
-pub type SymbolPowers = BTreeMap<Symbol, usize>;
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct Symbols(pub BTreeMap<SymbolPowers, BigRational>);
+/// A rational function of symbols, kept in lowest terms with a monic denominator.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Symbols {
+    pub numer: Polynomial,
+    pub denom: Polynomial,
+}
 
 pub fn lift_integer(x: isize) -> BigRational {
     BigRational::from(BigInt::from(x))
 }
 
-impl<'a> std::ops::Mul for &'a Symbols {
+impl From<Polynomial> for Symbols {
+    fn from(numer: Polynomial) -> Symbols {
+        Symbols {
+            numer,
+            denom: Polynomial::constant(lift_integer(1)),
+        }
+    }
+}
+
+/// A polynomial, or a quotient like `(a + b)/(c*d)` with brackets where they are needed.
+impl std::fmt::Display for Symbols {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_polynomial() {
+            return write!(f, "{}", self.numer);
+        }
+        if self.numer.0.len() > 1 {
+            write!(f, "({})/", self.numer)?;
+        } else {
+            write!(f, "{}/", self.numer)?;
+        }
+        let single_symbol = match self.denom.0.iter().next() {
+            Some((powers, _scale)) => {
+                self.denom.0.len() == 1 && powers.len() == 1 && powers.values().all(|p| *p == 1)
+            }
+            None => false,
+        };
+        if single_symbol {
+            write!(f, "{}", self.denom)
+        } else {
+            write!(f, "({})", self.denom)
+        }
+    }
+}
+
+impl std::str::FromStr for Symbols {
+    type Err = String;
+
+    /// Reads the `Display` form, see `parse::parse_symbols`.
+    fn from_str(src: &str) -> Result<Symbols, String> {
+        crate::parse::parse_symbols(src)
+    }
+}
+
+impl std::ops::Mul for &Symbols {
     type Output = Symbols;
 
-    fn mul(self, Symbols(rhs_powers): &Symbols) -> Symbols {
-        let Symbols(lhs_powers) = self;
-        lhs_powers
-            .iter()
-            .flat_map(|(lhs_power, lhs_scale)| {
-                rhs_powers.iter().map(move |(rhs_power, rhs_scale)| {
-                    (
-                        multiply_symbol_powers(lhs_power, &rhs_power),
-                        lhs_scale * rhs_scale,
-                    )
-                })
-            })
-            .fold(Symbols(BTreeMap::new()), |prev, (pwr, scale)| {
-                prev.add_scaled_power(scale, pwr)
-            })
+    fn mul(self, rhs: &Symbols) -> Symbols {
+        if self.denom.is_one() && rhs.denom.is_one() {
+            Symbols::reduced(
+                &self.numer * &rhs.numer,
+                Polynomial::constant(lift_integer(1)),
+            )
+        } else {
+            Symbols::reduced(&self.numer * &rhs.numer, &self.denom * &rhs.denom)
+        }
     }
 }
 
@@ -40,115 +84,660 @@ impl std::ops::Add for Symbols {
     type Output = Symbols;
 
     fn add(self, rhs: Symbols) -> Symbols {
-        self.0
-            .into_iter()
-            .chain(rhs.0.into_iter())
-            .fold(Symbols(BTreeMap::new()), |prev, (pows, scale)| {
-                prev.add_scaled_power(scale, pows)
-            })
+        if self.denom == rhs.denom {
+            Symbols::reduced(self.numer + rhs.numer, self.denom)
+        } else {
+            Symbols::reduced(
+                &self.numer * &rhs.denom + &rhs.numer * &self.denom,
+                &self.denom * &rhs.denom,
+            )
+        }
     }
 }
 
 impl Symbols {
-    fn add_scaled_power(mut self, scale: BigRational, power: SymbolPowers) -> Symbols {
-        let existing = self.0.remove(&power).unwrap_or_else(|| lift_integer(0));
-        let sum = existing + scale;
-        if !sum.is_zero() {
-            self.0.insert(power, sum);
+    pub fn zero() -> Symbols {
+        Symbols::from(Polynomial::default())
+    }
+
+    pub fn constant(value: BigRational) -> Symbols {
+        Symbols::from(Polynomial::constant(value))
+    }
+
+    pub fn symbol(sym: &str) -> Symbols {
+        Symbols::from(Polynomial::symbol(Symbol::from(sym)))
+    }
+
+    /// An opaque function application, panics unless `args` match the arity of `function`.
+    pub fn apply(function: Function, args: Vec<Symbols>) -> Symbols {
+        Symbols::try_apply(function, args).expect("Wrong number of arguments")
+    }
+
+    /// An opaque function application, fails unless `args` match the arity of `function`.
+    /// Constant arguments with exact results, like `sqrt(4)` or `cos(0)`, are evaluated.
+    pub fn try_apply(function: Function, args: Vec<Symbols>) -> Result<Symbols, String> {
+        function.check_arity(args.len())?;
+
+        if function == Function::IfZero {
+            match args[0].constant_value() {
+                Some(x) if x == lift_integer(0) => return Ok(args[1].clone()),
+                Some(_) => return Ok(args[2].clone()),
+                None if args[1] == args[2] => return Ok(args[1].clone()),
+                None => {}
+            }
         }
-        self
+
+        let constants: Option<Vec<BigRational>> =
+            args.iter().map(Symbols::constant_value).collect();
+        if let Some(value) = constants.and_then(|values| evaluate_exact(function, &values)) {
+            return Ok(Symbols::constant(value));
+        }
+
+        Ok(Symbols::from(Polynomial::symbol(Symbol::Apply(
+            function, args,
+        ))))
+    }
+
+    /// Fails when the denominator is zero.
+    pub fn new(numer: Polynomial, denom: Polynomial) -> Result<Symbols, String> {
+        let denom_is_zero = match apply_identities(&denom) {
+            Some(denom) => denom.is_zero(),
+            None => denom.is_zero(),
+        };
+        if denom_is_zero {
+            Err("Division by zero".to_string())
+        } else {
+            Ok(Symbols::reduced(numer, denom))
+        }
+    }
+
+    /// Applies identities and cancels common factors, the denominator must not be zero.
+    fn reduced(numer: Polynomial, denom: Polynomial) -> Symbols {
+        if numer.is_zero() {
+            return Symbols::zero();
+        }
+
+        match (apply_identities(&numer), apply_identities(&denom)) {
+            (None, None) => {}
+            (rewritten_numer, rewritten_denom) => {
+                let numer = rewritten_numer.unwrap_or_else(|| Symbols::from(numer));
+                let denom = rewritten_denom.unwrap_or_else(|| Symbols::from(denom));
+                return &numer * &denom.recip().expect("Denominator is not zero");
+            }
+        }
+
+        if let Some(value) = denom.constant_value() {
+            return Symbols::from(numer.scale(&value.recip()));
+        }
+
+        let common = numer.gcd(&denom);
+        let numer = numer.divide_exact(&common).expect("gcd divides numerator");
+        let denom = denom
+            .divide_exact(&common)
+            .expect("gcd divides denominator");
+
+        let (_powers, leading) = denom.leading_term().expect("Denominator is not zero");
+        let leading = leading.recip();
+        Symbols {
+            numer: numer.scale(&leading),
+            denom: denom.scale(&leading),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numer.is_zero()
+    }
+
+    pub fn is_polynomial(&self) -> bool {
+        self.denom.is_one()
+    }
+
+    /// The value when there are no symbols.
+    pub fn constant_value(&self) -> Option<BigRational> {
+        if self.is_polynomial() {
+            self.numer.constant_value()
+        } else {
+            None
+        }
+    }
+
+    /// The sign for all values of the symbols, if it can be told from the terms.
+    pub fn definite_sign(&self) -> Option<Ordering> {
+        match (self.numer.definite_sign()?, self.denom.definite_sign()?) {
+            (Ordering::Less, Ordering::Less) => Some(Ordering::Greater),
+            (Ordering::Less, _) | (_, Ordering::Less) => Some(Ordering::Less),
+            (numer, _) => Some(numer),
+        }
+    }
+
+    pub fn recip(&self) -> Result<Symbols, String> {
+        Symbols::new(self.denom.clone(), self.numer.clone())
+    }
+
+    /// Replaces named symbols by expressions, including inside function arguments.
+    pub fn substitute(&self, map: &BTreeMap<Symbol, Symbols>) -> Result<Symbols, String> {
+        let numer = substitute_polynomial(&self.numer, map)?;
+        let denom = substitute_polynomial(&self.denom, map)?;
+        Ok(&numer * &denom.recip()?)
+    }
+
+    /// The floating point value, every named symbol needs a value.
+    pub fn evaluate(&self, map: &BTreeMap<Symbol, f64>) -> Result<f64, String> {
+        let denom = evaluate_polynomial(&self.denom, map)?;
+        if denom == 0.0 {
+            return Err("Division by zero".to_string());
+        }
+        Ok(evaluate_polynomial(&self.numer, map)? / denom)
+    }
+
+    /// The exact value, fails when a function has no rational value at its arguments.
+    pub fn evaluate_rational(
+        &self,
+        map: &BTreeMap<Symbol, BigRational>,
+    ) -> Result<BigRational, String> {
+        let values = map
+            .iter()
+            .map(|(sym, value)| (sym.clone(), Symbols::constant(value.clone())))
+            .collect();
+        let value = self.substitute(&values)?;
+        value
+            .constant_value()
+            .ok_or_else(|| format!("No exact value, left with {:?}", value))
+    }
+
+    /// The partial derivative with respect to `var`, using the chain rule through function
+    /// terms. Fails where a function is not differentiable, like `acos` at a constant one.
+    pub fn derivative(&self, var: &Symbol) -> Result<Symbols, String> {
+        let numer = derivative_polynomial(&self.numer, var)?;
+        if self.is_polynomial() {
+            return Ok(numer);
+        }
+
+        // (n / d)' = (n' d - n d') / d^2
+        let denom = derivative_polynomial(&self.denom, var)?;
+        let n = Symbols::from(self.numer.clone());
+        let d = Symbols::from(self.denom.clone());
+        let top = &numer * &d + (&n * &denom).invert();
+        Ok(&top * &(&d * &d).recip()?)
+    }
+
+    /// An equal expression wherever the relations hold, the numerator and denominator are
+    /// reduced separately and kept only if that leaves fewer terms.
+    pub fn reduce(&self, relations: &Relations) -> Symbols {
+        let numer = relations.remainder(&self.numer);
+        if numer.is_zero() {
+            return Symbols::zero();
+        }
+        let denom = relations.remainder(&self.denom);
+        match Symbols::new(numer, denom) {
+            Ok(reduced) if reduced.term_count() <= self.term_count() => reduced,
+            _ => self.clone(),
+        }
+    }
+
+    fn term_count(&self) -> usize {
+        self.numer.0.len() + self.denom.0.len()
     }
 
     pub fn invert(&self) -> Self {
-        self * &Symbols(
-            vec![(BTreeMap::new(), lift_integer(-1))]
-                .into_iter()
-                .collect(),
-        )
+        self.scale(&lift_integer(-1))
+    }
+
+    pub fn scale(&self, factor: &BigRational) -> Self {
+        self * &Symbols::constant(factor.clone())
     }
 }
 
-fn multiply_symbol_powers(lhs: &SymbolPowers, rhs: &SymbolPowers) -> SymbolPowers {
-    lhs.iter()
-        .chain(rhs.iter())
-        .fold(BTreeMap::new(), |mut prev, (sym, pow)| {
-            let existing = prev.remove(sym).unwrap_or(0);
-            prev.insert(sym.to_string(), existing + pow);
-            prev
-        })
+fn substitute_polynomial(
+    poly: &Polynomial,
+    map: &BTreeMap<Symbol, Symbols>,
+) -> Result<Symbols, String> {
+    let mut sum = Symbols::zero();
+    for (powers, scale) in poly.0.iter() {
+        let mut term = Symbols::constant(scale.clone());
+        for (sym, pow) in powers.iter() {
+            term = &term * &power(&substitute_symbol(sym, map)?, *pow);
+        }
+        sum = sum + term;
+    }
+    Ok(sum)
+}
+
+fn substitute_symbol(sym: &Symbol, map: &BTreeMap<Symbol, Symbols>) -> Result<Symbols, String> {
+    match sym {
+        Symbol::Named(_) => Ok(map
+            .get(sym)
+            .cloned()
+            .unwrap_or_else(|| Symbols::from(Polynomial::symbol(sym.clone())))),
+        Symbol::Apply(function, args) => {
+            let args = args
+                .iter()
+                .map(|arg| arg.substitute(map))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Symbols::apply(*function, args))
+        }
+    }
+}
+
+fn evaluate_polynomial(poly: &Polynomial, map: &BTreeMap<Symbol, f64>) -> Result<f64, String> {
+    let mut sum = 0.0;
+    for (powers, scale) in poly.0.iter() {
+        let mut term = scale.to_f64().ok_or("Coefficient does not fit in f64")?;
+        for (sym, pow) in powers.iter() {
+            term *= evaluate_symbol(sym, map)?.powi(*pow as i32);
+        }
+        sum += term;
+    }
+    Ok(sum)
+}
+
+fn evaluate_symbol(sym: &Symbol, map: &BTreeMap<Symbol, f64>) -> Result<f64, String> {
+    match sym {
+        Symbol::Named(name) => map
+            .get(sym)
+            .cloned()
+            .ok_or_else(|| format!("No value for symbol '{}'", name)),
+        // Only the chosen branch, the other may divide by zero
+        Symbol::Apply(Function::IfZero, args) => {
+            if args[0].evaluate(map)? == 0.0 {
+                args[1].evaluate(map)
+            } else {
+                args[2].evaluate(map)
+            }
+        }
+        Symbol::Apply(function, args) => {
+            let args = args
+                .iter()
+                .map(|arg| arg.evaluate(map))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(function.evaluate(&args))
+        }
+    }
+}
+
+fn derivative_polynomial(poly: &Polynomial, var: &Symbol) -> Result<Symbols, String> {
+    let mut sum = Symbols::zero();
+    for (powers, scale) in poly.0.iter() {
+        for (sym, pow) in powers.iter() {
+            let inner = derivative_symbol(sym, var)?;
+            if inner.is_zero() {
+                continue;
+            }
+
+            let mut rest = powers.clone();
+            if *pow > 1 {
+                rest.insert(sym.clone(), pow - 1);
+            } else {
+                rest.remove(sym);
+            }
+            let outer = Polynomial(
+                vec![(rest, scale * lift_integer(*pow as isize))]
+                    .into_iter()
+                    .collect(),
+            );
+            sum = sum + &Symbols::from(outer) * &inner;
+        }
+    }
+    Ok(sum)
+}
+
+fn derivative_symbol(sym: &Symbol, var: &Symbol) -> Result<Symbols, String> {
+    if sym == var {
+        return Ok(Symbols::constant(lift_integer(1)));
+    }
+
+    match sym {
+        Symbol::Named(_) => Ok(Symbols::zero()),
+        // By branch, as the unused branch may not be finite
+        Symbol::Apply(Function::IfZero, args) => Ok(Symbols::apply(
+            Function::IfZero,
+            vec![
+                args[0].clone(),
+                args[1].derivative(var)?,
+                args[2].derivative(var)?,
+            ],
+        )),
+        Symbol::Apply(function, args) => {
+            let mut sum = Symbols::zero();
+            for (partial, arg) in partial_derivatives(*function, args)?.iter().zip(args) {
+                sum = sum + partial * &arg.derivative(var)?;
+            }
+            Ok(sum)
+        }
+    }
+}
+
+/// The derivative of a function with respect to each of its arguments.
+fn partial_derivatives(function: Function, args: &[Symbols]) -> Result<Vec<Symbols>, String> {
+    let one = Symbols::constant(lift_integer(1));
+    let apply = |function| Symbols::apply(function, args.to_vec());
+    let u = &args[0];
+    let u_squared = u * u;
+    let partial = match function {
+        Function::Sqrt => apply(Function::Sqrt).scale(&lift_integer(2)).recip()?,
+        Function::Sin => apply(Function::Cos),
+        Function::Cos => apply(Function::Sin).invert(),
+        Function::Tan => one.clone() + &apply(Function::Tan) * &apply(Function::Tan),
+        Function::Asin => sqrt(one.clone() + u_squared.invert()).recip()?,
+        Function::Acos => sqrt(one.clone() + u_squared.invert()).recip()?.invert(),
+        Function::Atan => (one.clone() + u_squared).recip()?,
+        Function::Atan2 => {
+            // atan2(y, x) has partials x / (xx + yy) and -y / (xx + yy)
+            let x = &args[1];
+            let norm = (u_squared + x * x).recip()?;
+            return Ok(vec![x * &norm, (u * &norm).invert()]);
+        }
+        Function::Sinh => apply(Function::Cosh),
+        Function::Cosh => apply(Function::Sinh),
+        Function::Tanh => one.clone() + (&apply(Function::Tanh) * &apply(Function::Tanh)).invert(),
+        Function::Asinh => sqrt(u_squared + one.clone()).recip()?,
+        Function::Acosh => sqrt(u_squared + one.invert()).recip()?,
+        Function::Atanh => (one.clone() + u_squared.invert()).recip()?,
+        Function::Exp => apply(Function::Exp),
+        Function::Ln => u.recip()?,
+        Function::IfZero => unreachable!("if_zero is differentiated by branch"),
+    };
+    Ok(vec![partial])
+}
+
+fn sqrt(syms: Symbols) -> Symbols {
+    Symbols::apply(Function::Sqrt, vec![syms])
+}
+
+/// Rewrites `sqrt(x)^2` as `x`, `cos(t)^2` as `1 - sin(t)^2` and `cosh(t)^2` as `1 + sinh(t)^2`
+/// so that identities like `sin(t)^2 + cos(t)^2 = 1` cancel. None when there is nothing to do.
+fn apply_identities(poly: &Polynomial) -> Option<Symbols> {
+    let rewrites = poly.0.keys().any(|powers| {
+        powers
+            .iter()
+            .any(|(sym, pow)| *pow >= 2 && squared(sym).is_some())
+    });
+    if !rewrites {
+        return None;
+    }
+
+    let mut sum = Symbols::zero();
+    for (powers, scale) in poly.0.iter() {
+        let mut term = Symbols::constant(scale.clone());
+        for (sym, pow) in powers.iter() {
+            let sym_poly = Symbols::from(Polynomial::symbol(sym.clone()));
+            term = match squared(sym) {
+                Some(square) => &(&term * &power(&square, pow / 2)) * &power(&sym_poly, pow % 2),
+                None => &term * &power(&sym_poly, *pow),
+            };
+        }
+        sum = sum + term;
+    }
+    Some(sum)
+}
+
+/// The square of a function term, when it can be written without that term.
+fn squared(sym: &Symbol) -> Option<Symbols> {
+    match sym {
+        Symbol::Apply(Function::Sqrt, args) => Some(args[0].clone()),
+        Symbol::Apply(Function::Cos, args) => {
+            let sin = Symbols::apply(Function::Sin, args.clone());
+            Some(Symbols::constant(lift_integer(1)) + (&sin * &sin).invert())
+        }
+        Symbol::Apply(Function::Cosh, args) => {
+            let sinh = Symbols::apply(Function::Sinh, args.clone());
+            Some(Symbols::constant(lift_integer(1)) + &sinh * &sinh)
+        }
+        _ => None,
+    }
+}
+
+fn power(syms: &Symbols, pow: usize) -> Symbols {
+    (0..pow).fold(Symbols::constant(lift_integer(1)), |prev, _| &prev * syms)
+}
+
+/// Function values at constants that are themselves rational.
+fn evaluate_exact(function: Function, args: &[BigRational]) -> Option<BigRational> {
+    let zero = lift_integer(0);
+    let one = lift_integer(1);
+    match (function, args) {
+        (Function::Sqrt, [x]) if x >= &zero => {
+            let numer = x.numer().sqrt();
+            let denom = x.denom().sqrt();
+            let root = BigRational::new(numer, denom);
+            if &(&root * &root) == x {
+                Some(root)
+            } else {
+                None
+            }
+        }
+        (Function::Cos, [x]) | (Function::Cosh, [x]) | (Function::Exp, [x]) if x == &zero => {
+            Some(one)
+        }
+        (Function::Acos, [x]) | (Function::Acosh, [x]) | (Function::Ln, [x]) if x == &one => {
+            Some(zero)
+        }
+        (Function::Acos, _) | (Function::Acosh, _) | (Function::Ln, _) => None,
+        (Function::Atan2, _) => None,
+        (_, [x]) if x == &zero => Some(zero),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn sum(terms: &[(isize, &str)]) -> Symbols {
+        terms.iter().fold(Symbols::zero(), |prev, (scale, sym)| {
+            let term = if sym.is_empty() {
+                Symbols::constant(lift_integer(1))
+            } else {
+                Symbols::symbol(sym)
+            };
+            prev + term.scale(&lift_integer(*scale))
+        })
+    }
+
     #[test]
-    fn test_simple_multiply_symbol_powers() {
-        let lhs: SymbolPowers = vec![("x".to_string(), 2), ("y".to_string(), 3)]
-            .into_iter()
-            .collect();
-        let rhs: SymbolPowers = vec![("y".to_string(), 4), ("z".to_string(), 5)]
-            .into_iter()
-            .collect();
+    fn test_apply_checks_arity() {
+        let a = Symbols::symbol("a");
+        assert!(Symbols::try_apply(Function::Atan2, vec![a.clone()]).is_err());
+        assert!(Symbols::try_apply(Function::IfZero, vec![a.clone(), a.clone()]).is_err());
+        assert_eq!(
+            Symbols::try_apply(Function::Sin, vec![a.clone()]),
+            Ok(Symbols::apply(Function::Sin, vec![a]))
+        );
+    }
+
+    #[test]
+    fn test_cancel_common_factors() -> Result<(), String> {
+        // (aa - bb) / (2a + 2b) = (a - b) / 2
+        let a = Symbols::symbol("a");
+        let b = Symbols::symbol("b");
+        let numer = &a * &a + (&b * &b).invert();
+        let denom = sum(&[(2, "a"), (2, "b")]);
 
+        let quotient = &numer * &denom.recip()?;
+        assert!(quotient.is_polynomial());
         assert_eq!(
-            multiply_symbol_powers(&lhs, &rhs),
-            vec![
-                ("x".to_string(), 2),
-                ("y".to_string(), 3 + 4),
-                ("z".to_string(), 5)
-            ]
-            .into_iter()
-            .collect()
+            quotient,
+            sum(&[(1, "a"), (-1, "b")]).scale(&BigRational::new(1.into(), 2.into()))
         );
+        Ok(())
     }
 
     #[test]
-    fn test_mult_power_increase() {
-        // (2 + x) * (3 + xx) = 6 + 2xx + 3x + xxx
-        let lhs = Symbols(
-            vec![
-                (
-                    vec![("x".to_string(), 1)].into_iter().collect(),
-                    lift_integer(1),
-                ),
-                (BTreeMap::new(), lift_integer(2)),
-            ]
-            .into_iter()
-            .collect(),
+    fn test_add_fractions() -> Result<(), String> {
+        // 1 / a + 1 / b = (a + b) / ab, and a / (a + b) + b / (a + b) = 1
+        let a = Symbols::symbol("a");
+        let b = Symbols::symbol("b");
+        let total = a.recip()? + b.recip()?;
+        assert_eq!(total, &sum(&[(1, "a"), (1, "b")]) * &(&a * &b).recip()?);
+
+        let a_plus_b = sum(&[(1, "a"), (1, "b")]).recip()?;
+        assert_eq!(
+            &a * &a_plus_b + &b * &a_plus_b,
+            Symbols::constant(lift_integer(1))
         );
-        let rhs = Symbols(
-            vec![
-                (
-                    vec![("x".to_string(), 2)].into_iter().collect(),
-                    lift_integer(1),
-                ),
-                (BTreeMap::new(), lift_integer(3)),
-            ]
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), String> {
+        let a_plus_b = sum(&[(1, "a"), (1, "b")]);
+        assert_eq!(
+            (&a_plus_b * &sum(&[(1, "c")]).recip()?).to_string(),
+            "(a + b)/c"
+        );
+        assert_eq!(
+            (&sum(&[(-1, "a")]) * &(&a_plus_b * &sum(&[(2, "c")])).recip()?).to_string(),
+            "-1/2*a/(a*c + b*c)"
+        );
+        let root = Symbols::apply(Function::Sqrt, vec![&a_plus_b * &a_plus_b]);
+        assert_eq!(root.to_string(), "sqrt(a^2 + 2*a*b + b^2)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_recip_of_zero_fails() {
+        assert!(Symbols::zero().recip().is_err());
+    }
+
+    #[test]
+    fn test_function_identities() -> Result<(), String> {
+        let x = Symbols::symbol("x");
+        let t = Symbols::symbol("t");
+        let one = Symbols::constant(lift_integer(1));
+
+        let sqrt = Symbols::apply(Function::Sqrt, vec![x.clone()]);
+        assert_eq!(&sqrt * &sqrt, x);
+        assert_eq!(&(&sqrt * &x) * &sqrt.recip()?, x);
+
+        let sin = Symbols::apply(Function::Sin, vec![t.clone()]);
+        let cos = Symbols::apply(Function::Cos, vec![t.clone()]);
+        assert_eq!(&sin * &sin + &cos * &cos, one);
+
+        let sinh = Symbols::apply(Function::Sinh, vec![t.clone()]);
+        let cosh = Symbols::apply(Function::Cosh, vec![t]);
+        assert_eq!(&cosh * &cosh + (&sinh * &sinh).invert(), one);
+        assert!((&cos * &cos + sin.invert()).definite_sign().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_evaluates_exact_constants() {
+        let constant = |n: isize, d: isize| Symbols::constant(BigRational::new(n.into(), d.into()));
+
+        assert_eq!(
+            Symbols::apply(Function::Sqrt, vec![constant(9, 4)]),
+            constant(3, 2)
+        );
+        assert!(Symbols::apply(Function::Sqrt, vec![constant(2, 1)])
+            .constant_value()
+            .is_none());
+        assert!(Symbols::apply(Function::Sqrt, vec![constant(-4, 1)])
+            .constant_value()
+            .is_none());
+        assert_eq!(
+            Symbols::apply(Function::Cos, vec![constant(0, 1)]),
+            constant(1, 1)
+        );
+        assert_eq!(
+            Symbols::apply(Function::Ln, vec![constant(1, 1)]),
+            Symbols::zero()
+        );
+        assert_eq!(
+            Symbols::apply(Function::Sqrt, vec![Symbols::symbol("x")]).definite_sign(),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn test_substitute_and_evaluate() -> Result<(), String> {
+        // (xx + sqrt(y)) / (x - 1) with x = a + 1 is ((a + 1)^2 + sqrt(y)) / a
+        let x = Symbols::symbol("x");
+        let sqrt_y = Symbols::apply(Function::Sqrt, vec![Symbols::symbol("y")]);
+        let f = &(&x * &x + sqrt_y.clone()) * &sum(&[(1, "x"), (-1, "")]).recip()?;
+
+        let a_plus_one = sum(&[(1, "a"), (1, "")]);
+        let map = vec![(Symbol::from("x"), a_plus_one.clone())]
             .into_iter()
-            .collect(),
+            .collect();
+        let expected = &(&a_plus_one * &a_plus_one + sqrt_y) * &Symbols::symbol("a").recip()?;
+        assert_eq!(f.substitute(&map)?, expected);
+
+        let exact = vec![
+            (Symbol::from("x"), lift_integer(3)),
+            (Symbol::from("y"), lift_integer(4)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            f.evaluate_rational(&exact)?,
+            BigRational::new(11.into(), 2.into())
         );
 
-        let expected = Symbols(
-            vec![
-                (
-                    vec![("x".to_string(), 3)].into_iter().collect(),
-                    lift_integer(1),
-                ),
-                (
-                    vec![("x".to_string(), 2)].into_iter().collect(),
-                    lift_integer(2),
-                ),
-                (
-                    vec![("x".to_string(), 1)].into_iter().collect(),
-                    lift_integer(3),
-                ),
-                (BTreeMap::new(), lift_integer(6)),
-            ]
+        let values = vec![(Symbol::from("x"), 3.0), (Symbol::from("y"), 2.0)]
+            .into_iter()
+            .collect();
+        assert!((f.evaluate(&values)? - (9.0 + 2f64.sqrt()) / 2.0).abs() < 1e-12);
+
+        let missing = vec![(Symbol::from("x"), 3.0)].into_iter().collect();
+        assert!(f.evaluate(&missing).is_err());
+        let pole = vec![(Symbol::from("x"), 1.0), (Symbol::from("y"), 2.0)]
             .into_iter()
-            .collect(),
+            .collect();
+        assert!(f.evaluate(&pole).is_err());
+        let irrational = vec![
+            (Symbol::from("x"), lift_integer(3)),
+            (Symbol::from("y"), lift_integer(2)),
+        ]
+        .into_iter()
+        .collect();
+        assert!(f.evaluate_rational(&irrational).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_derivative_rules() -> Result<(), String> {
+        let x = Symbols::symbol("x");
+        let y = Symbols::symbol("y");
+        let var = Symbol::from("x");
+
+        // d/dx (xxy + 1/x) = 2xy - 1/xx
+        let f = &(&x * &x) * &y + x.recip()?;
+        let expected = (&x * &y).scale(&lift_integer(2)) + (&x * &x).recip()?.invert();
+        assert_eq!(f.derivative(&var)?, expected);
+        assert_eq!(f.derivative(&Symbol::from("z"))?, Symbols::zero());
+
+        // d/dx sin(xx) = 2x cos(xx)
+        let sin = Symbols::apply(Function::Sin, vec![&x * &x]);
+        let cos = Symbols::apply(Function::Cos, vec![&x * &x]);
+        assert_eq!(sin.derivative(&var)?, &x.scale(&lift_integer(2)) * &cos);
+
+        // d/dx sqrt(x) = 1 / (2 sqrt(x))
+        let sqrt = Symbols::apply(Function::Sqrt, vec![x.clone()]);
+        assert_eq!(
+            sqrt.derivative(&var)?,
+            sqrt.scale(&lift_integer(2)).recip()?
         );
+        Ok(())
+    }
 
-        assert_eq!(&lhs * &rhs, expected);
+    #[test]
+    fn test_derivative_matches_finite_difference() -> Result<(), String> {
+        // atan2(y, x) exp(x) / sqrt(1 + xx)
+        let x = Symbols::symbol("x");
+        let y = Symbols::symbol("y");
+        let f = &(&Symbols::apply(Function::Atan2, vec![y, x.clone()])
+            * &Symbols::apply(Function::Exp, vec![x.clone()]))
+            * &Symbols::apply(Function::Sqrt, vec![sum(&[(1, "")]) + &x * &x]).recip()?;
+        let df = f.derivative(&Symbol::from("x"))?;
+
+        let at = |x: f64| -> BTreeMap<Symbol, f64> {
+            vec![(Symbol::from("x"), x), (Symbol::from("y"), 0.7)]
+                .into_iter()
+                .collect()
+        };
+        let h = 1e-6;
+        let estimate = (f.evaluate(&at(0.3 + h))? - f.evaluate(&at(0.3 - h))?) / (2.0 * h);
+        assert!((df.evaluate(&at(0.3))? - estimate).abs() < 1e-6);
+        Ok(())
     }
 }